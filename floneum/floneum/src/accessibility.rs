@@ -0,0 +1,216 @@
+use std::{collections::HashSet, fmt::Display};
+
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::ValueType;
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx, visit::EdgeRef};
+
+use crate::connect::{DropTarget, HoverValidity};
+use crate::graph::CurrentlyDragging;
+use crate::{
+    use_application_state, CurrentlyDraggingProps, DraggingIndex, Node, Point, VisualGraph,
+    VisualGraphInner,
+};
+
+/// How far the arrow keys move the focused node
+pub const KEYBOARD_NUDGE: f32 = 16.;
+
+/// What a node is doing, as it is announced to screen readers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NodeStatus<'a> {
+    Running,
+    Queued,
+    Failed(&'a str),
+    Outdated,
+    UpToDate,
+}
+
+impl<'a> NodeStatus<'a> {
+    pub fn of(node: &'a Node) -> Self {
+        if node.running {
+            NodeStatus::Running
+        } else if node.queued {
+            NodeStatus::Queued
+        } else if let Some(error) = &node.error {
+            NodeStatus::Failed(error)
+        } else if node.dirty {
+            NodeStatus::Outdated
+        } else {
+            NodeStatus::UpToDate
+        }
+    }
+}
+
+impl Display for NodeStatus<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeStatus::Running => write!(f, "running"),
+            NodeStatus::Queued => write!(f, "waiting to run"),
+            NodeStatus::Failed(error) => write!(f, "failed: {error}"),
+            NodeStatus::Outdated => write!(f, "outdated"),
+            NodeStatus::UpToDate => write!(f, "up to date"),
+        }
+    }
+}
+
+/// The name of a type as it is read out, like "text" or "list of number"
+pub fn type_label(ty: ValueType) -> String {
+    match ty {
+        ValueType::Single(ty) => format!("{ty:?}").to_lowercase(),
+        ValueType::Many(ty) => format!("list of {}", format!("{ty:?}").to_lowercase()),
+    }
+}
+
+/// How many sockets of a node are connected
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ConnectionSummary {
+    pub connected_inputs: usize,
+    pub inputs: usize,
+    pub connected_outputs: usize,
+    pub outputs: usize,
+}
+
+pub fn node_label(name: &str, status: NodeStatus, connections: ConnectionSummary) -> String {
+    let ConnectionSummary {
+        connected_inputs,
+        inputs,
+        connected_outputs,
+        outputs,
+    } = connections;
+    format!(
+        "{name}, {status}, {connected_inputs} of {inputs} inputs connected, {connected_outputs} of {outputs} outputs connected"
+    )
+}
+
+pub fn socket_label(kind: &str, name: &str, ty: Option<ValueType>, connected: bool) -> String {
+    let ty = ty.map(type_label).unwrap_or_else(|| "unknown".to_string());
+    let connected = if connected {
+        "connected"
+    } else {
+        "not connected"
+    };
+    format!("{kind} {name}, {ty}, {connected}")
+}
+
+impl VisualGraphInner {
+    pub fn connection_summary(&self, id: NodeIndex<DefaultIx>) -> ConnectionSummary {
+        let Some(node) = self.graph.node_weight(id) else {
+            return ConnectionSummary::default();
+        };
+        let node = node.read();
+        let connected_inputs: HashSet<_> = self
+            .graph
+            .edges_directed(id, petgraph::Direction::Incoming)
+            .map(|edge| edge.weight().read().end.index)
+            .collect();
+        let connected_outputs: HashSet<_> = self
+            .graph
+            .edges_directed(id, petgraph::Direction::Outgoing)
+            .map(|edge| edge.weight().read().start)
+            .collect();
+        ConnectionSummary {
+            connected_inputs: connected_inputs.len(),
+            inputs: node.inputs.len(),
+            connected_outputs: connected_outputs.len(),
+            outputs: node.outputs.len(),
+        }
+    }
+
+    pub fn input_is_connected(&self, id: NodeIndex<DefaultIx>, index: usize) -> bool {
+        self.graph
+            .edges_directed(id, petgraph::Direction::Incoming)
+            .any(|edge| edge.weight().read().end.index == index)
+    }
+}
+
+/// The offset the arrow keys move a node by, the keyboard equivalent of dragging it
+pub fn nudge(key: &Key) -> Option<Point> {
+    match key {
+        Key::ArrowLeft => Some(Point::new(-KEYBOARD_NUDGE, 0.)),
+        Key::ArrowRight => Some(Point::new(KEYBOARD_NUDGE, 0.)),
+        Key::ArrowUp => Some(Point::new(0., -KEYBOARD_NUDGE)),
+        Key::ArrowDown => Some(Point::new(0., KEYBOARD_NUDGE)),
+        _ => None,
+    }
+}
+
+impl VisualGraph {
+    /// The keyboard equivalent of dragging a connection. Pressing enter on a socket picks up a
+    /// connection from it, and pressing enter on a second socket drops the connection there
+    pub fn toggle_keyboard_connection(&mut self, node: Signal<Node>, index: DraggingIndex) {
+        let dragging_connection = matches!(
+            self.inner.read().currently_dragging,
+            Some(CurrentlyDragging::Connection(_))
+        );
+        if dragging_connection {
+            let id = node.read().id;
+            self.finish_connection(id, DropTarget::Socket(index));
+            return;
+        }
+        // Start from the socket on the macro if the node is collapsed into one
+        let id = node.read().id;
+        let position = match index {
+            DraggingIndex::Input(connection) => self.inner.read().input_anchor(id, connection),
+            DraggingIndex::Output(output) => self.inner.read().output_anchor(id, output),
+        };
+        self.inner.write().currently_dragging =
+            Some(CurrentlyDragging::Connection(CurrentlyDraggingProps {
+                from: node,
+                from_pos: position,
+                index,
+                to: Signal::new(position),
+                hover: Signal::new(HoverValidity::Empty),
+            }));
+    }
+}
+
+/// Announces when the focused node starts running, finishes or fails
+pub fn LiveRegion() -> Element {
+    let application = use_application_state();
+    let announcement = application
+        .read()
+        .currently_focused
+        .map(|focused| {
+            let node = focused.node.read();
+            format!(
+                "{} {}",
+                node.instance.metadata().name,
+                NodeStatus::of(&node)
+            )
+        })
+        .unwrap_or_default();
+
+    rsx! {
+        div {
+            role: "status",
+            aria_live: "polite",
+            // Visually hidden, but still read by screen readers
+            style: "position: absolute; width: 1px; height: 1px; overflow: hidden; clip: rect(0 0 0 0); white-space: nowrap;",
+            "{announcement}"
+        }
+    }
+}
+
+#[test]
+fn labels_describe_status_and_connections() {
+    use floneum_plugin::plugins::main::types::PrimitiveValueType;
+
+    let connections = ConnectionSummary {
+        connected_inputs: 1,
+        inputs: 2,
+        connected_outputs: 0,
+        outputs: 1,
+    };
+    assert_eq!(
+        node_label("Generate Text", NodeStatus::Failed("no model"), connections),
+        "Generate Text, failed: no model, 1 of 2 inputs connected, 0 of 1 outputs connected"
+    );
+    assert_eq!(
+        socket_label(
+            "Input",
+            "prompt",
+            Some(ValueType::Many(PrimitiveValueType::Text)),
+            false
+        ),
+        "Input prompt, list of text, not connected"
+    );
+}
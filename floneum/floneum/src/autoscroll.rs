@@ -0,0 +1,141 @@
+use dioxus::html::geometry::{
+    euclid::{Rect, Vector2D},
+    PagePoint,
+};
+use dioxus::prelude::spawn;
+use std::time::Duration;
+
+use crate::pin::Viewport;
+use crate::{Point, VisualGraph};
+
+/// How far from the edge of the canvas the cursor starts scrolling the viewport
+const EDGE_SCROLL_ZONE: f32 = 48.;
+/// How many pixels the viewport moves each frame when the cursor is right at the edge
+const MAX_EDGE_SCROLL_SPEED: f32 = 12.;
+const EDGE_SCROLL_FRAME: Duration = Duration::from_millis(16);
+
+/// The viewport is scrolling because something is being dragged near the edge of the canvas
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EdgeScroll {
+    /// How far the viewport moves each frame
+    pub velocity: Vector2D<f32, f32>,
+    /// The last position of the cursor on the page
+    pub cursor: PagePoint,
+}
+
+/// How fast the viewport should scroll with the cursor at this position. The closer the cursor
+/// is to an edge, the faster the viewport scrolls towards it
+pub fn edge_scroll_velocity(viewport: Rect<f32, f32>, cursor: Point) -> Vector2D<f32, f32> {
+    let speed = |distance: f32| MAX_EDGE_SCROLL_SPEED * (1. - distance.max(0.) / EDGE_SCROLL_ZONE);
+    let axis = |position: f32, min: f32, max: f32| {
+        if position - min < EDGE_SCROLL_ZONE {
+            -speed(position - min)
+        } else if max - position < EDGE_SCROLL_ZONE {
+            speed(max - position)
+        } else {
+            0.
+        }
+    };
+    Vector2D::new(
+        axis(cursor.x, viewport.min_x(), viewport.max_x()),
+        axis(cursor.y, viewport.min_y(), viewport.max_y()),
+    )
+}
+
+impl VisualGraph {
+    /// Scroll the viewport every frame until the drag ends or the cursor leaves the edge of the
+    /// canvas
+    pub fn start_edge_scroll(&self) {
+        let mut graph = *self;
+        spawn(async move {
+            loop {
+                tokio::time::sleep(EDGE_SCROLL_FRAME).await;
+                if !graph.step_edge_scroll() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Scroll the viewport one frame if something is being dragged near the edge of the canvas.
+    /// Returns false once the viewport stopped scrolling
+    pub fn step_edge_scroll(&mut self) -> bool {
+        let (scroll, dragging) = {
+            let inner = self.inner.read();
+            (inner.edge_scroll, inner.currently_dragging.is_some())
+        };
+        let Some(scroll) = scroll else {
+            return false;
+        };
+        if !dragging {
+            self.inner.write().edge_scroll = None;
+            return false;
+        }
+        {
+            let mut inner = self.inner.write();
+            let viewport = inner.viewport();
+            inner.set_viewport(Viewport {
+                pan_pos: viewport.pan_pos - scroll.velocity,
+                ..viewport
+            });
+        }
+        // The cursor didn't move, but the graph moved under it
+        self.drag_to(scroll.cursor);
+        self.inner.read().edge_scroll.is_some()
+    }
+}
+
+#[test]
+fn scrolls_faster_closer_to_the_edge() {
+    use dioxus::html::geometry::euclid::Size2D;
+
+    let viewport = Rect::new(Point::new(0., 0.), Size2D::new(400., 300.));
+    assert_eq!(
+        edge_scroll_velocity(viewport, Point::new(200., 150.)),
+        Vector2D::zero()
+    );
+    let near = edge_scroll_velocity(viewport, Point::new(390., 150.));
+    let nearer = edge_scroll_velocity(viewport, Point::new(399., 150.));
+    assert!(near.x > 0. && nearer.x > near.x);
+    assert_eq!(near.y, 0.);
+    let top_left = edge_scroll_velocity(viewport, Point::new(-10., 0.));
+    assert_eq!(
+        top_left,
+        Vector2D::new(-MAX_EDGE_SCROLL_SPEED, -MAX_EDGE_SCROLL_SPEED)
+    );
+}
+
+#[test]
+fn scrolling_stops_when_the_drag_ends() {
+    use crate::graph::{CurrentlyDragging, NodeDragInfo};
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use dioxus::html::geometry::euclid::{Point2D, Size2D};
+
+    run_in_dioxus(|| async {
+        let mut graph = VisualGraph::default();
+        let string = test_plugin("string");
+        let id = graph.add_node(&string, Point::new(0., 0.)).await.unwrap();
+        {
+            let mut inner = graph.inner.write();
+            inner.rendered_size = Some(Rect::new(Point2D::new(0., 0.), Size2D::new(400., 300.)));
+            let node = inner.graph[id];
+            inner.currently_dragging = Some(CurrentlyDragging::Node(NodeDragInfo {
+                element_offset: Point::new(0., 0.),
+                node,
+            }));
+        }
+
+        // Nothing scrolls away from the edges
+        graph.drag_to(PagePoint::new(200., 150.));
+        assert!(!graph.step_edge_scroll());
+
+        graph.drag_to(PagePoint::new(399., 150.));
+        let pan_before = graph.inner.read().pan_pos;
+        assert!(graph.step_edge_scroll());
+        assert!(graph.inner.read().pan_pos.x < pan_before.x);
+
+        graph.clear_dragging();
+        assert!(!graph.step_edge_scroll());
+        assert!(graph.inner.read().edge_scroll.is_none());
+    });
+}
@@ -0,0 +1,152 @@
+use anyhow::anyhow;
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::PrimitiveValue;
+use floneum_plugin::Plugin;
+use petgraph::{
+    graph::NodeIndex,
+    stable_graph::{DefaultIx, EdgeIndex},
+};
+
+use crate::connect::{check_connection, CheckedConnection, ConnectError};
+use crate::edge::{Connection, ConnectionType};
+use crate::shape::GraphShape;
+use crate::{edit_input, DraggingIndex, Point, VisualGraph};
+
+/// Check a connection from an output to an input with the same rules as dragging a connection in
+/// the canvas
+pub fn plan_connection(
+    graph: &GraphShape,
+    from: NodeIndex<DefaultIx>,
+    output: usize,
+    to: NodeIndex<DefaultIx>,
+    input: usize,
+) -> Result<CheckedConnection, ConnectError> {
+    let input = Connection {
+        index: input,
+        ty: ConnectionType::Single,
+    };
+    check_connection(
+        graph,
+        (from, DraggingIndex::Output(output)),
+        (to, DraggingIndex::Input(input)),
+    )
+}
+
+/// Build graphs without going through the UI
+impl VisualGraph {
+    /// Add a node running the plugin at a position in graph space
+    pub async fn add_node(
+        &self,
+        plugin: &Plugin,
+        position: Point,
+    ) -> anyhow::Result<NodeIndex<DefaultIx>> {
+        let instance = plugin.instance().await?;
+        let id = self.create_node(instance)?;
+        let mut node = self.inner.read().graph[id];
+        node.write().position = position;
+        Ok(id)
+    }
+
+    /// Connect an output of one node to an input of another node. Any other connection to the
+    /// input is replaced
+    pub fn connect(
+        &mut self,
+        from: NodeIndex<DefaultIx>,
+        output: usize,
+        to: NodeIndex<DefaultIx>,
+        input: usize,
+    ) -> Result<EdgeIndex<DefaultIx>, ConnectError> {
+        let mut inner = self.inner.write();
+        let CheckedConnection {
+            source,
+            target,
+            edge,
+        } = plan_connection(&inner.shape(), from, output, to, input)?;
+        Ok(inner.insert_edge(source, target, Signal::new(edge)))
+    }
+
+    /// Set the value of an input that isn't connected to anything. Like editing it by hand, this
+    /// queues the node to run with the new value
+    pub fn set_input_value(
+        &self,
+        node: NodeIndex<DefaultIx>,
+        index: usize,
+        value: PrimitiveValue,
+    ) -> anyhow::Result<()> {
+        let current_node = *self
+            .inner
+            .read()
+            .graph
+            .node_weight(node)
+            .ok_or_else(|| anyhow!("Node {:?} doesn't exist", node))?;
+        let input = *current_node
+            .read()
+            .inputs
+            .get(index)
+            .ok_or_else(|| anyhow!("Node {:?} has no input {}", node, index))?;
+        edit_input(input, current_node, |current| *current = vec![vec![value]]);
+        Ok(())
+    }
+}
+
+#[test]
+fn small_graph_is_built_and_validated() {
+    use crate::testing::{input, run_in_dioxus, test_plugin};
+
+    run_in_dioxus(|| async {
+        let mut graph = VisualGraph::default();
+        let string = test_plugin("string");
+        let source = graph.add_node(&string, Point::new(0., 0.)).await.unwrap();
+        let middle = graph.add_node(&string, Point::new(200., 0.)).await.unwrap();
+        let sink = graph.add_node(&string, Point::new(400., 0.)).await.unwrap();
+        let numbers = graph
+            .add_node(&test_plugin("number"), Point::new(0., 200.))
+            .await
+            .unwrap();
+        assert_eq!(
+            graph.inner.read().graph[middle].read().position,
+            Point::new(200., 0.)
+        );
+
+        graph.connect(source, 0, middle, 0).unwrap();
+        let middle_to_sink = graph.connect(middle, 0, sink, 0).unwrap();
+        // Connecting the same sockets again keeps the existing connection
+        assert_eq!(graph.connect(middle, 0, sink, 0).unwrap(), middle_to_sink);
+
+        let mut error = |from, output, to| graph.connect(from, output, to, 0).unwrap_err();
+        assert_eq!(error(source, 0, numbers), ConnectError::IncompatibleTypes);
+        assert_eq!(error(sink, 0, middle), ConnectError::Cycle);
+        assert_eq!(error(middle, 0, middle), ConnectError::SelfLoop);
+        assert_eq!(error(source, 1, sink), ConnectError::NoSocket);
+
+        // Connecting to an input that is already connected replaces the old connection
+        graph.connect(source, 0, sink, 0).unwrap();
+        {
+            let inner = graph.inner.read();
+            assert_eq!(inner.graph.edge_count(), 2);
+            assert!(inner.edge_exists(source, 0, sink, input(0)));
+            assert!(!inner.edge_exists(middle, 0, sink, input(0)));
+        }
+
+        graph
+            .set_input_value(numbers, 0, PrimitiveValue::Number(3))
+            .unwrap();
+        {
+            let inner = graph.inner.read();
+            let node = inner.graph[numbers].read();
+            assert!(node.dirty);
+            assert_eq!(
+                node.inputs[0].read().value,
+                vec![vec![PrimitiveValue::Number(3)]]
+            );
+        }
+        assert!(graph
+            .set_input_value(numbers, 1, PrimitiveValue::Number(3))
+            .is_err());
+
+        // Only the node that was never connected is left over
+        let problems = graph.validate();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].node, numbers);
+    });
+}
@@ -0,0 +1,432 @@
+use std::fmt::Display;
+
+use dioxus::prelude::*;
+use petgraph::{
+    algo::has_path_connecting,
+    graph::NodeIndex,
+    stable_graph::{DefaultIx, EdgeIndex},
+    visit::EdgeRef,
+};
+
+use crate::edge::{Connection, ConnectionType};
+use crate::graph::CurrentlyDraggingProps;
+use crate::shape::GraphShape;
+use crate::{DraggingIndex, Edge, Node, Point, VisualGraphInner};
+
+/// Where a connection that is being dragged was dropped on a node
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DropTarget {
+    /// The connection was dropped directly on a socket
+    Socket(DraggingIndex),
+    /// The connection was dropped somewhere on the body of the node
+    Point(Point),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectError {
+    /// Both ends of the connection are inputs or both ends are outputs
+    WrongKind,
+    /// The node doesn't have a socket that the connection could be attached to
+    NoSocket,
+    /// The output type cannot be passed to the input
+    IncompatibleTypes,
+    /// The connection starts and ends on the same node
+    SelfLoop,
+    /// The connection would make the graph cyclic
+    Cycle,
+}
+
+impl Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectError::WrongKind => write!(f, "an input must be connected to an output"),
+            ConnectError::NoSocket => write!(f, "there is no socket to connect to"),
+            ConnectError::IncompatibleTypes => write!(f, "the types are not compatible"),
+            ConnectError::SelfLoop => write!(f, "a node cannot be connected to itself"),
+            ConnectError::Cycle => write!(f, "the connection would create a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
+/// What would happen if the connection that is being dragged was dropped right now
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum HoverValidity {
+    /// The cursor isn't near any socket
+    #[default]
+    Empty,
+    /// The cursor is near a socket the connection can be attached to
+    Valid,
+    /// The cursor is near a socket that would reject the connection
+    Invalid,
+}
+
+/// A connection that passed every check in [`check_connection`]
+#[derive(Clone, Debug)]
+pub struct CheckedConnection {
+    pub source: NodeIndex<DefaultIx>,
+    pub target: NodeIndex<DefaultIx>,
+    pub edge: Edge,
+}
+
+/// Check if a connection between two sockets would be valid
+pub fn check_connection(
+    graph: &GraphShape,
+    from: (NodeIndex<DefaultIx>, DraggingIndex),
+    to: (NodeIndex<DefaultIx>, DraggingIndex),
+) -> Result<CheckedConnection, ConnectError> {
+    let ((source, output), (target, input)) = match (from.1, to.1) {
+        (DraggingIndex::Output(output), DraggingIndex::Input(input)) => {
+            ((from.0, output), (to.0, input))
+        }
+        (DraggingIndex::Input(input), DraggingIndex::Output(output)) => {
+            ((to.0, output), (from.0, input))
+        }
+        _ => return Err(ConnectError::WrongKind),
+    };
+    if source == target {
+        return Err(ConnectError::SelfLoop);
+    }
+    let output_ty = graph
+        .node_weight(source)
+        .and_then(|node| node.output_type(output))
+        .ok_or(ConnectError::NoSocket)?;
+    let input_ty = graph
+        .node_weight(target)
+        .and_then(|node| node.input_type(input))
+        .ok_or(ConnectError::NoSocket)?;
+    if !output_ty.compatible(&input_ty) {
+        return Err(ConnectError::IncompatibleTypes);
+    }
+    if has_path_connecting(graph, target, source, None) {
+        return Err(ConnectError::Cycle);
+    }
+
+    Ok(CheckedConnection {
+        source,
+        target,
+        edge: Edge::new(output, input),
+    })
+}
+
+/// The sockets on a node that a connection dragged from `dragging` could be attached to
+fn opposite_sockets(node: &Node, dragging: DraggingIndex) -> Vec<(DraggingIndex, Point)> {
+    match dragging {
+        DraggingIndex::Output(_) => (0..node.inputs.len())
+            .map(|index| {
+                let connection = Connection {
+                    index,
+                    ty: ConnectionType::Single,
+                };
+                (DraggingIndex::Input(connection), node.input_pos(connection))
+            })
+            .collect(),
+        DraggingIndex::Input(_) => (0..node.outputs.len())
+            .map(|index| (DraggingIndex::Output(index), node.output_pos(index)))
+            .collect(),
+    }
+}
+
+/// Find the socket closest to a point
+pub fn nearest_socket(
+    sockets: impl IntoIterator<Item = (DraggingIndex, Point)>,
+    point: Point,
+) -> Option<DraggingIndex> {
+    sockets
+        .into_iter()
+        .min_by(|(_, a), (_, b)| {
+            (*a - point)
+                .square_length()
+                .total_cmp(&(*b - point).square_length())
+        })
+        .map(|(index, _)| index)
+}
+
+/// Find the socket closest to a point if it is close enough for a dragged connection to snap to it
+pub fn snap_to_socket<T>(
+    sockets: impl IntoIterator<Item = (T, Point)>,
+    point: Point,
+    snap_distance: f32,
+) -> Option<T> {
+    sockets
+        .into_iter()
+        .map(|(socket, pos)| (socket, (pos - point).length()))
+        .filter(|(_, distance)| *distance <= snap_distance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(socket, _)| socket)
+}
+
+impl VisualGraphInner {
+    /// Check if an output of one node is already connected to an input of another node
+    pub fn edge_exists(
+        &self,
+        source: NodeIndex<DefaultIx>,
+        output: usize,
+        target: NodeIndex<DefaultIx>,
+        input: Connection,
+    ) -> bool {
+        let edge = Edge::new(output, input);
+        self.graph
+            .edges_connecting(source, target)
+            .any(|existing| *existing.weight().read() == edge)
+    }
+
+    /// Try to finish a connection that is being dragged on a node. Every way of dropping a
+    /// connection goes through this method so the same checks are always applied
+    pub fn complete_connection(
+        &mut self,
+        drag: &CurrentlyDraggingProps,
+        target_node: NodeIndex<DefaultIx>,
+        target: DropTarget,
+    ) -> Result<EdgeIndex<DefaultIx>, ConnectError> {
+        let from_id = drag.from.read().id;
+        let to_index = match target {
+            DropTarget::Socket(index) => index,
+            DropTarget::Point(point) => {
+                let node = self
+                    .graph
+                    .node_weight(target_node)
+                    .ok_or(ConnectError::NoSocket)?
+                    .read();
+                // Attach to the closest socket of the opposite kind
+                nearest_socket(opposite_sockets(&node, drag.index), point)
+                    .ok_or(ConnectError::NoSocket)?
+            }
+        };
+        let checked = check_connection(
+            &self.shape(),
+            (from_id, drag.index),
+            (target_node, to_index),
+        )?;
+        Ok(self.insert_edge(checked.source, checked.target, Signal::new(checked.edge)))
+    }
+
+    /// The socket a connection that is being dragged snaps to when the cursor is at a point
+    pub fn snap_target(
+        &self,
+        drag: &CurrentlyDraggingProps,
+        point: Point,
+    ) -> Option<(NodeIndex<DefaultIx>, DraggingIndex)> {
+        let sockets = self
+            .graph
+            .node_indices()
+            // Nodes inside a macro are hidden, so they can't be hovered
+            .filter(|&id| self.macro_containing(id).is_none())
+            .flat_map(|id| {
+                let sockets = opposite_sockets(&self.graph[id].read(), drag.index);
+                sockets
+                    .into_iter()
+                    .map(move |(index, pos)| ((id, index), pos))
+            });
+        snap_to_socket(sockets, point, self.settings.snap_distance)
+    }
+
+    /// Check the socket closest to the cursor while a connection is being dragged
+    pub fn hover_validity(&self, drag: &CurrentlyDraggingProps, point: Point) -> HoverValidity {
+        let from_id = drag.from.read().id;
+        match self.snap_target(drag, point) {
+            Some((id, index)) => {
+                match check_connection(&self.shape(), (from_id, drag.index), (id, index)) {
+                    Ok(_) => HoverValidity::Valid,
+                    Err(_) => HoverValidity::Invalid,
+                }
+            }
+            None => HoverValidity::Empty,
+        }
+    }
+
+    /// Add an edge, replacing any edge that is already connected to the same input. If the same
+    /// connection already exists, the existing edge is kept instead of adding a duplicate
+    pub fn insert_edge(
+        &mut self,
+        source: NodeIndex<DefaultIx>,
+        target: NodeIndex<DefaultIx>,
+        edge: Signal<Edge>,
+    ) -> EdgeIndex<DefaultIx> {
+        let Edge { start, end } = edge.read().clone();
+        let edges_to_remove: Vec<_> = self
+            .graph
+            .edges_directed(target, petgraph::Direction::Incoming)
+            .filter(|existing| existing.weight().read().end == end)
+            .map(|existing| existing.id())
+            .collect();
+        // An input only has one edge, so an identical connection is the edge that would be replaced
+        if let [existing] = edges_to_remove[..] {
+            if self.edge_exists(source, start, target, end) {
+                return existing;
+            }
+        }
+        for existing in edges_to_remove {
+            self.graph.remove_edge(existing);
+        }
+        self.graph.add_edge(source, target, edge)
+    }
+
+    /// Remove an edge. The node it fed is marked dirty because its input changed. Returns false if
+    /// the edge doesn't exist
+    pub fn remove_connection(&mut self, edge: EdgeIndex<DefaultIx>) -> bool {
+        let Some((_, target)) = self.graph.edge_endpoints(edge) else {
+            return false;
+        };
+        self.graph.remove_edge(edge);
+        let mut target = self.graph[target];
+        target.write().dirty = true;
+        true
+    }
+}
+
+#[cfg(test)]
+use crate::testing::{input, test_graph};
+
+#[test]
+fn wrong_kind_is_rejected() {
+    let (graph, [first, _, numbers]) = test_graph();
+    let result = check_connection(
+        &graph,
+        (first, DraggingIndex::Input(input(0))),
+        (numbers, DraggingIndex::Input(input(0))),
+    );
+    assert_eq!(result.unwrap_err(), ConnectError::WrongKind);
+}
+
+#[test]
+fn incompatible_types_are_rejected() {
+    let (graph, [first, _, numbers]) = test_graph();
+    let result = check_connection(
+        &graph,
+        (first, DraggingIndex::Output(0)),
+        (numbers, DraggingIndex::Input(input(0))),
+    );
+    assert_eq!(result.unwrap_err(), ConnectError::IncompatibleTypes);
+}
+
+#[test]
+fn self_loops_are_rejected() {
+    let (graph, [first, _, _]) = test_graph();
+    let result = check_connection(
+        &graph,
+        (first, DraggingIndex::Output(0)),
+        (first, DraggingIndex::Input(input(0))),
+    );
+    assert_eq!(result.unwrap_err(), ConnectError::SelfLoop);
+}
+
+#[test]
+fn cycles_are_rejected() {
+    let (graph, [first, second, _]) = test_graph();
+    // Dragging from the input of the first node to the output of the second node
+    let result = check_connection(
+        &graph,
+        (first, DraggingIndex::Input(input(0))),
+        (second, DraggingIndex::Output(0)),
+    );
+    assert_eq!(result.unwrap_err(), ConnectError::Cycle);
+}
+
+#[test]
+fn missing_sockets_are_rejected() {
+    let (graph, [first, second, _]) = test_graph();
+    let result = check_connection(
+        &graph,
+        (first, DraggingIndex::Output(3)),
+        (second, DraggingIndex::Input(input(0))),
+    );
+    assert_eq!(result.unwrap_err(), ConnectError::NoSocket);
+    assert_eq!(nearest_socket([], Point::new(0., 0.)), None);
+}
+
+#[test]
+fn valid_connection_is_accepted() {
+    let (graph, [first, second, _]) = test_graph();
+    let checked = check_connection(
+        &graph,
+        (second, DraggingIndex::Input(input(0))),
+        (first, DraggingIndex::Output(0)),
+    )
+    .unwrap();
+    assert_eq!(checked.source, first);
+    assert_eq!(checked.target, second);
+    assert_eq!(checked.edge.start, 0);
+    assert_eq!(checked.edge.end, input(0));
+}
+
+#[test]
+fn larger_snap_distance_reaches_farther_sockets() {
+    use crate::settings::GraphSettings;
+
+    let default = GraphSettings::default().snap_distance;
+    let point = Point::new(0., 0.);
+    let sockets = || {
+        [
+            ("farther", Point::new(default * 3., 0.)),
+            ("closer", Point::new(0., default + 1.)),
+        ]
+    };
+    // A socket just out of reach of the default snap distance
+    assert_eq!(snap_to_socket(sockets(), point, default), None);
+    assert_eq!(
+        snap_to_socket(sockets(), point, default * 2.),
+        Some("closer")
+    );
+    // The closest socket wins when several are in reach
+    assert_eq!(
+        snap_to_socket(sockets(), point, default * 4.),
+        Some("closer")
+    );
+}
+
+#[test]
+fn identical_connections_are_not_duplicated() {
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use crate::VisualGraph;
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let number = graph
+            .add_node(&test_plugin("number"), Point::new(0., 0.))
+            .await
+            .unwrap();
+        let add = graph
+            .add_node(&test_plugin("add"), Point::new(200., 0.))
+            .await
+            .unwrap();
+        let mut inner = graph.inner;
+        let mut inner = inner.write();
+        let mut connect =
+            |socket| inner.insert_edge(number, add, Signal::new(Edge::new(0, socket)));
+        let existing = connect(input(0));
+        assert_eq!(connect(input(0)), existing);
+        let other_input = connect(input(1));
+        assert_ne!(other_input, existing);
+        assert_eq!(inner.graph.edge_count(), 2);
+        assert!(inner.edge_exists(number, 0, add, input(0)));
+        assert!(inner.edge_exists(number, 0, add, input(1)));
+    });
+}
+
+#[test]
+fn removing_a_connection_marks_its_target_dirty() {
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use crate::VisualGraph;
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let number = test_plugin("number");
+        let source = graph.add_node(&number, Point::new(0., 0.)).await.unwrap();
+        let target = graph.add_node(&number, Point::new(200., 0.)).await.unwrap();
+        let mut inner = graph.inner;
+        let mut inner = inner.write();
+        let edge = inner.insert_edge(source, target, Signal::new(Edge::new(0, input(0))));
+        for id in [source, target] {
+            let mut node = inner.graph[id];
+            node.write().dirty = false;
+        }
+
+        assert!(inner.remove_connection(edge));
+        assert_eq!(inner.graph.edge_count(), 0);
+        assert!(inner.graph[target].read().dirty);
+        assert!(!inner.graph[source].read().dirty);
+        assert!(!inner.remove_connection(edge));
+    });
+}
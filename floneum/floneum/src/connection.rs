@@ -1,21 +1,200 @@
 use dioxus::html::geometry::euclid::Point2D;
 use dioxus::prelude::*;
+use petgraph::stable_graph::{DefaultIx, EdgeIndex};
+
+use crate::crossings::HOP_RADIUS;
+use crate::settings::RoutingMode;
+use crate::VisualGraphInner;
+
+/// How many line segments a curve is split into to find where it crosses other connections
+const CURVE_SAMPLES: usize = 12;
+
+impl VisualGraphInner {
+    /// If a connection carries an outdated value because the node it comes from changed or failed
+    /// since it last ran
+    pub fn edge_is_stale(&self, edge: EdgeIndex<DefaultIx>) -> bool {
+        self.graph
+            .edge_endpoints(edge)
+            .is_some_and(|(source, _)| self.graph[source].read().is_stale())
+    }
+}
 
 #[component]
 pub fn Connection(
     start_pos: Point2D<f32, f32>,
     end_pos: Point2D<f32, f32>,
     color: String,
+    stale: bool,
+    routing: RoutingMode,
+    /// Where the connection hops over connections that are drawn before it
+    #[props(default)]
+    hops: Vec<Point2D<f32, f32>>,
 ) -> Element {
-    let offset = (end_pos.x - start_pos.x) / 2.0;
+    let path = connection_path(start_pos, end_pos, routing);
+    let hops: Vec<_> = hops
+        .into_iter()
+        .map(|hop| {
+            format!(
+                "M{},{} A{HOP_RADIUS},{HOP_RADIUS} 0 0 1 {},{}",
+                hop.x - HOP_RADIUS,
+                hop.y,
+                hop.x + HOP_RADIUS,
+                hop.y
+            )
+        })
+        .collect();
+
+    if stale {
+        return rsx! {
+            path {
+                class: "connection-stale",
+                d: "{path}",
+                fill: "none",
+                stroke: "rgb(156, 163, 175)",
+                stroke_width: "2",
+                stroke_dasharray: "6 4",
+                pointer_events: "none"
+            }
+            for hop in hops {
+                Hop { path: hop, color: "rgb(156, 163, 175)".to_string() }
+            }
+        };
+    }
 
     rsx! {
         path {
-            d: "M{start_pos.x},{start_pos.y} C{start_pos.x + offset},{start_pos.y} {end_pos.x - offset},{end_pos.y} {end_pos.x},{end_pos.y}",
+            d: "{path}",
             fill: "none",
             stroke: "{color}",
             stroke_width: "2",
             pointer_events: "none"
         }
+        for hop in hops {
+            Hop { path: hop, color: color.clone() }
+        }
     }
 }
+
+/// A semicircle over the place a connection crosses another connection. The wider background
+/// stroke hides the other connection under the hop
+#[component]
+fn Hop(path: String, color: String) -> Element {
+    rsx! {
+        path {
+            d: "{path}",
+            fill: "none",
+            stroke: "white",
+            stroke_width: "5",
+            pointer_events: "none"
+        }
+        path {
+            d: "{path}",
+            fill: "none",
+            stroke: "{color}",
+            stroke_width: "2",
+            pointer_events: "none"
+        }
+    }
+}
+
+pub fn connection_path(
+    start_pos: Point2D<f32, f32>,
+    end_pos: Point2D<f32, f32>,
+    routing: RoutingMode,
+) -> String {
+    if routing == RoutingMode::Straight {
+        return format!(
+            "M{},{} L{},{}",
+            start_pos.x, start_pos.y, end_pos.x, end_pos.y
+        );
+    }
+    let offset = (end_pos.x - start_pos.x) / 2.0;
+    format!(
+        "M{},{} C{},{} {},{} {},{}",
+        start_pos.x,
+        start_pos.y,
+        start_pos.x + offset,
+        start_pos.y,
+        end_pos.x - offset,
+        end_pos.y,
+        end_pos.x,
+        end_pos.y
+    )
+}
+
+/// Points along the connection, close enough together that straight lines between them follow
+/// the curve
+pub fn connection_polyline(
+    start_pos: Point2D<f32, f32>,
+    end_pos: Point2D<f32, f32>,
+    routing: RoutingMode,
+) -> Vec<Point2D<f32, f32>> {
+    if routing == RoutingMode::Straight {
+        return vec![start_pos, end_pos];
+    }
+    // The same control points as the curve in connection_path
+    let offset = (end_pos.x - start_pos.x) / 2.0;
+    let control_start = Point2D::new(start_pos.x + offset, start_pos.y);
+    let control_end = Point2D::new(end_pos.x - offset, end_pos.y);
+    (0..=CURVE_SAMPLES)
+        .map(|i| {
+            let t = i as f32 / CURVE_SAMPLES as f32;
+            let a = start_pos.lerp(control_start, t);
+            let b = control_start.lerp(control_end, t);
+            let c = control_end.lerp(end_pos, t);
+            a.lerp(b, t).lerp(b.lerp(c, t), t)
+        })
+        .collect()
+}
+
+/// The point halfway along the connection. The control points of curves are mirrored around the
+/// center of the curve, so this is just the midpoint of the two ends for either routing mode
+pub fn connection_midpoint(
+    start_pos: Point2D<f32, f32>,
+    end_pos: Point2D<f32, f32>,
+) -> Point2D<f32, f32> {
+    start_pos.lerp(end_pos, 0.5)
+}
+
+#[test]
+fn changing_an_input_makes_outgoing_connections_stale() {
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use crate::{edit_input, VisualGraph};
+    use floneum_plugin::plugins::main::types::PrimitiveValue;
+
+    run_in_dioxus(|| async {
+        let mut graph = VisualGraph::default();
+        let string = test_plugin("string");
+        let first = graph.add_node(&string, Point2D::new(0., 0.)).await.unwrap();
+        let second = graph
+            .add_node(&string, Point2D::new(200., 0.))
+            .await
+            .unwrap();
+        let third = graph
+            .add_node(&string, Point2D::new(400., 0.))
+            .await
+            .unwrap();
+        let first_to_second = graph.connect(first, 0, second, 0).unwrap();
+        let second_to_third = graph.connect(second, 0, third, 0).unwrap();
+        let stale = |edge| graph.inner.read().edge_is_stale(edge);
+        assert!(!stale(first_to_second) && !stale(second_to_third));
+
+        // Editing a value in the input editor
+        let node = graph.inner.read().graph[first];
+        let input = node.read().inputs[0];
+        edit_input(input, node, |value| {
+            *value = vec![vec![PrimitiveValue::Text("changed".to_string())]]
+        });
+        assert!(node.read().dirty);
+        assert!(node.read().queued);
+        assert!(stale(first_to_second));
+        assert!(!stale(second_to_third));
+
+        // Setting a value through the builder
+        graph
+            .set_input_value(second, 0, PrimitiveValue::Text("changed".to_string()))
+            .unwrap();
+        assert!(graph.inner.read().graph[second].read().dirty);
+        assert!(stale(second_to_third));
+    });
+}
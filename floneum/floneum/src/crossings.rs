@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use dioxus::html::geometry::euclid::Rect;
+use petgraph::stable_graph::{DefaultIx, EdgeIndex};
+
+use crate::connection::connection_polyline;
+use crate::settings::RoutingMode;
+use crate::Point;
+
+/// The radius of the semicircle drawn where a connection hops over another connection
+pub const HOP_RADIUS: f32 = 4.;
+
+/// Where two line segments cross, if they do
+pub fn segment_intersection(a: (Point, Point), b: (Point, Point)) -> Option<Point> {
+    let r = a.1 - a.0;
+    let s = b.1 - b.0;
+    let denominator = r.cross(s);
+    if denominator.abs() <= f32::EPSILON {
+        // Parallel segments never cross at a single point
+        return None;
+    }
+    let offset = b.0 - a.0;
+    let t = offset.cross(s) / denominator;
+    let u = offset.cross(r) / denominator;
+    ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)).then(|| a.0 + r * t)
+}
+
+fn polyline_bounds(points: &[Point]) -> Rect<f32, f32> {
+    Rect::from_points(points)
+}
+
+/// For every path, the points where it crosses a path that is drawn before it. Crossings near the
+/// ends of a path are ignored because connections that share a socket always meet there
+pub fn path_crossings(paths: &[Vec<Point>]) -> Vec<Vec<Point>> {
+    let bounds: Vec<_> = paths.iter().map(|path| polyline_bounds(path)).collect();
+    let near_an_end = |path: &[Point], point: Point| {
+        [path.first(), path.last()]
+            .into_iter()
+            .flatten()
+            .any(|end| (*end - point).length() < HOP_RADIUS * 2.)
+    };
+    paths
+        .iter()
+        .enumerate()
+        .map(|(later, path)| {
+            let mut hops = Vec::new();
+            // Inflate the bounds so perfectly horizontal or vertical paths still intersect
+            let inflated = bounds[later].inflate(1., 1.);
+            for (other, other_bounds) in paths[..later].iter().zip(&bounds[..later]) {
+                if !inflated.intersects(&other_bounds.inflate(1., 1.)) {
+                    continue;
+                }
+                for segment in path.windows(2) {
+                    for other_segment in other.windows(2) {
+                        let crossing = segment_intersection(
+                            (segment[0], segment[1]),
+                            (other_segment[0], other_segment[1]),
+                        );
+                        if let Some(crossing) = crossing {
+                            if !near_an_end(path, crossing) && !near_an_end(other, crossing) {
+                                hops.push(crossing);
+                            }
+                        }
+                    }
+                }
+            }
+            hops
+        })
+        .collect()
+}
+
+/// The hops of every connection, by the order the connections are drawn in
+pub fn connection_hops(
+    connections: &[(EdgeIndex<DefaultIx>, Point, Point)],
+    routing: RoutingMode,
+) -> HashMap<EdgeIndex<DefaultIx>, Vec<Point>> {
+    let paths: Vec<_> = connections
+        .iter()
+        .map(|&(_, start, end)| connection_polyline(start, end, routing))
+        .collect();
+    connections
+        .iter()
+        .zip(path_crossings(&paths))
+        .filter(|(_, hops)| !hops.is_empty())
+        .map(|(&(id, _, _), hops)| (id, hops))
+        .collect()
+}
+
+#[test]
+fn later_connection_hops_over_earlier_connection() {
+    let line = |x1, y1, x2, y2| vec![Point::new(x1, y1), Point::new(x2, y2)];
+    let close = |a: Point, b: Point| (a - b).length() < 0.01;
+    let paths = [
+        line(0., 0., 100., 100.),
+        line(0., 100., 100., 0.),
+        // Parallel to the first path
+        line(10., 0., 110., 100.),
+        // Starts at the same socket as the first path
+        line(0., 0., 100., 60.),
+    ];
+    let crossings = path_crossings(&paths);
+    assert!(crossings[0].is_empty());
+    assert_eq!(crossings[1].len(), 1);
+    assert!(close(crossings[1][0], Point::new(50., 50.)));
+    // The third path crosses the second path, but not the first
+    assert_eq!(crossings[2].len(), 1);
+    assert!(close(crossings[2][0], Point::new(55., 45.)));
+    // The fourth path meets the first path at the shared socket, but that isn't a crossing
+    assert_eq!(crossings[3].len(), 2);
+    assert!(close(crossings[3][0], Point::new(62.5, 37.5)));
+    assert!(close(crossings[3][1], Point::new(25., 15.)));
+}
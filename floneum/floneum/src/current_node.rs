@@ -62,7 +62,7 @@ pub fn CurrentNodeInfo() -> Element {
                             h2 { class: "text-xl font-bold", "inputs:" }
                             for & node in &node.inputs {
                                 div {
-                                    ModifyInput { key: "{node.read().definition.name}", node }
+                                    ModifyInput { key: "{node.read().definition.name}", node, owner: node_info.node }
                                 }
                             }
                         }
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use dioxus::{html::geometry::euclid::Vector2D, prelude::*};
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx, visit::EdgeRef};
+
+use crate::shape::GraphShape;
+use crate::undo::UndoStep;
+use crate::{ApplicationState, Edge};
+
+/// How far duplicates are moved from the nodes they were copied from
+const DUPLICATE_OFFSET: f32 = 24.;
+
+/// The nodes and connections a duplicate of the selection is made of
+#[derive(Clone, Debug)]
+pub struct DuplicatePlan {
+    /// The nodes to copy, in the order the copies are created
+    pub nodes: Vec<NodeIndex<DefaultIx>>,
+    /// Connections between the copied nodes, as indices into `nodes`. Connections to nodes outside
+    /// of the selection are not copied
+    pub edges: Vec<(usize, usize, Edge)>,
+}
+
+pub fn plan_duplicate(graph: &GraphShape, selection: &[NodeIndex<DefaultIx>]) -> DuplicatePlan {
+    let nodes: Vec<_> = selection
+        .iter()
+        .copied()
+        .filter(|&id| graph.contains_node(id))
+        .collect();
+    let position: HashMap<_, _> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, &id)| (id, index))
+        .collect();
+    let edges = graph
+        .edge_references()
+        .filter_map(|edge| {
+            let source = *position.get(&edge.source())?;
+            let target = *position.get(&edge.target())?;
+            Some((source, target, edge.weight().clone()))
+        })
+        .collect();
+    DuplicatePlan { nodes, edges }
+}
+
+impl ApplicationState {
+    /// The nodes shortcuts act on. This is the selection, or the focused node if nothing is
+    /// selected
+    pub fn selected_nodes(&self) -> Vec<NodeIndex<DefaultIx>> {
+        let selection = self.graph.inner.read().selection.clone();
+        if !selection.is_empty() {
+            return selection;
+        }
+        self.currently_focused
+            .map(|focused| vec![focused.node.read().id])
+            .unwrap_or_default()
+    }
+}
+
+/// Copy the selected nodes with their input values and the connections between them. The copies
+/// become the new selection and are recorded as one undo step
+pub async fn duplicate_selection(mut application: Signal<ApplicationState>) -> anyhow::Result<()> {
+    let graph = application.read().graph;
+    let selection = application.read().selected_nodes();
+    let plan = plan_duplicate(&graph.inner.read().shape(), &selection);
+    if plan.nodes.is_empty() {
+        return Ok(());
+    }
+
+    let mut copies = Vec::new();
+    for &original in &plan.nodes {
+        let original = graph.inner.read().graph[original];
+        let name = original.read().instance.metadata().name.clone();
+        // Clone the plugin so the application state isn't borrowed while the instance is created
+        let plugin = application
+            .read()
+            .get_plugin(&name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Plugin {} is not loaded", name))?;
+        let position = original.read().position + Vector2D::new(DUPLICATE_OFFSET, DUPLICATE_OFFSET);
+        let id = graph.add_node(&plugin, position).await?;
+        let mut copy = graph.inner.read().graph[id];
+        {
+            let original = original.read();
+            let mut copy = copy.write();
+            copy.dirty = true;
+            for (from, to) in original.inputs.iter().zip(copy.inputs.iter()) {
+                to.write_unchecked().value.clone_from(&from.read().value);
+            }
+        }
+        copies.push(id);
+    }
+
+    let mut inner = graph.inner;
+    let mut inner = inner.write();
+    for (source, target, edge) in plan.edges {
+        inner.insert_edge(copies[source], copies[target], Signal::new(edge));
+    }
+    for &id in &copies {
+        inner.bring_to_front(id);
+    }
+    inner.selection = copies.clone();
+    drop(inner);
+    let mut application = application.write();
+    application.currently_focused = None;
+    application.undo_history.record(UndoStep::AddNodes(copies));
+    Ok(())
+}
+
+#[test]
+fn only_edges_inside_the_selection_are_planned() {
+    use crate::shape::NodeShape;
+    use crate::testing::{input, text};
+
+    let mut graph = GraphShape::default();
+    let outside = graph.add_node(NodeShape::test(&[], &[text()]));
+    let first = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let second = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    graph.add_edge(outside, first, Edge::new(0, input(0)));
+    graph.add_edge(first, second, Edge::new(0, input(0)));
+
+    let plan = plan_duplicate(&graph, &[first, second]);
+    assert_eq!(plan.nodes, [first, second]);
+    assert_eq!(plan.edges.len(), 1);
+    let (source, target, edge) = &plan.edges[0];
+    assert_eq!((*source, *target), (0, 1));
+    assert_eq!((edge.start, edge.end), (0, input(0)));
+}
+
+#[test]
+fn duplicate_selection_copies_nodes_values_and_inner_edges() {
+    use crate::testing::{input, run_in_dioxus, test_plugin};
+    use floneum_plugin::plugins::main::types::PrimitiveValue;
+
+    run_in_dioxus(|| async {
+        let plugin = test_plugin("string");
+        let name = plugin.name().await.unwrap();
+        let mut state = ApplicationState::default();
+        state.add_plugin(plugin).await.unwrap();
+        let outside = state.insert_plugin(&name).await.unwrap();
+        let first = state.insert_plugin(&name).await.unwrap();
+        let second = state.insert_plugin(&name).await.unwrap();
+        let mut graph = state.graph;
+        graph.connect(outside, 0, first, 0).unwrap();
+        graph.connect(first, 0, second, 0).unwrap();
+        let value = PrimitiveValue::Text("hello".to_string());
+        graph.set_input_value(second, 0, value.clone()).unwrap();
+        graph.inner.write().selection = vec![first, second];
+
+        let mut state = Signal::new(state);
+        duplicate_selection(state).await.unwrap();
+
+        let inner = graph.inner.read();
+        assert_eq!(inner.graph.node_count(), 5);
+        assert_eq!(inner.graph.edge_count(), 3);
+        let copies = inner.selection.clone();
+        assert_eq!(copies.len(), 2);
+        assert!(!copies.contains(&first) && !copies.contains(&second));
+        assert!(inner.edge_exists(copies[0], 0, copies[1], input(0)));
+        // The connection from outside of the selection is not copied
+        assert_eq!(
+            inner
+                .graph
+                .edges_directed(copies[0], petgraph::Direction::Incoming)
+                .count(),
+            0
+        );
+
+        let copy = inner.graph[copies[1]].read();
+        assert_eq!(copy.inputs[0].read().value, vec![vec![value]]);
+        assert!(copy.dirty);
+        let offset = Vector2D::new(DUPLICATE_OFFSET, DUPLICATE_OFFSET);
+        assert_eq!(copy.position, inner.graph[second].read().position + offset);
+        drop(copy);
+        drop(inner);
+
+        // Undoing removes both copies and the connection between them in one step
+        assert!(state.write().undo());
+        let inner = graph.inner.read();
+        assert_eq!(inner.graph.node_count(), 3);
+        assert_eq!(inner.graph.edge_count(), 2);
+        assert!(copies.iter().all(|&copy| !inner.graph.contains_node(copy)));
+        drop(inner);
+        assert!(!state.write().undo());
+    });
+}
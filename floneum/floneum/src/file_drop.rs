@@ -0,0 +1,125 @@
+use dioxus::html::FileEngine;
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::PrimitiveValue;
+use floneumite::FloneumPackageIndex;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{insert_plugin_from_entry, ApplicationState, Point};
+
+/// Extensions of files that are read as plain text
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "csv", "json", "toml", "yaml", "yml", "html", "xml", "log", "rs", "py", "js", "ts",
+];
+
+/// Extensions of images and other binary files. They can't be read as text, so the node gets the
+/// path of the file instead
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "pdf", "wav", "mp3", "mp4", "zip",
+];
+
+/// How a dropped file is turned into a node
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileSource {
+    /// The contents of the file become the value of a text node
+    Text,
+    /// The path of the file becomes the value of a node with a file input
+    Path,
+}
+
+impl FileSource {
+    /// The plugin used to load the file
+    pub fn plugin(self) -> &'static str {
+        match self {
+            FileSource::Text => "String",
+            FileSource::Path => "Read From File",
+        }
+    }
+}
+
+pub fn source_for_file(path: &Path) -> Option<FileSource> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    if TEXT_EXTENSIONS.contains(&extension.as_str()) {
+        Some(FileSource::Text)
+    } else if BINARY_EXTENSIONS.contains(&extension.as_str()) {
+        Some(FileSource::Path)
+    } else {
+        None
+    }
+}
+
+/// Create a node for a file that was dropped on the canvas
+pub async fn create_node_from_file(
+    mut application: Signal<ApplicationState>,
+    plugins: Option<Rc<FloneumPackageIndex>>,
+    files: Arc<dyn FileEngine>,
+    path: String,
+    position: Point,
+) {
+    let file_name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let Some(source) = source_for_file(Path::new(&path)) else {
+        application
+            .write()
+            .notifications
+            .push(format!("{file_name} isn't a supported file type"));
+        return;
+    };
+    let plugin_name = source.plugin();
+    let entry = plugins.and_then(|plugins| {
+        plugins
+            .entries()
+            .iter()
+            .find(|entry| entry.meta().is_some_and(|meta| meta.name == plugin_name))
+            .cloned()
+    });
+    let Some(entry) = entry else {
+        application.write().notifications.push(format!(
+            "The {plugin_name} plugin is needed to add {file_name}"
+        ));
+        return;
+    };
+    let value = match source {
+        FileSource::Text => match files.read_file_to_string(&path).await {
+            Some(contents) => PrimitiveValue::Text(contents),
+            None => {
+                application
+                    .write()
+                    .notifications
+                    .push(format!("Failed to read {file_name}"));
+                return;
+            }
+        },
+        FileSource::Path => PrimitiveValue::File(path),
+    };
+    let id = match insert_plugin_from_entry(application, entry).await {
+        Ok(id) => id,
+        Err(err) => {
+            log::error!("Failed to insert plugin: {}", err);
+            return;
+        }
+    };
+    let graph = application.read().graph;
+    {
+        let mut node = graph.inner.read().graph[id];
+        node.write().position = position;
+    }
+    if let Err(err) = graph.set_input_value(id, 0, value) {
+        log::error!("Failed to set the contents of {}: {}", file_name, err);
+    }
+}
+
+#[test]
+fn dropped_files_pick_a_source_by_extension() {
+    let source = |path: &str| source_for_file(Path::new(path));
+    assert_eq!(source("notes.TXT"), Some(FileSource::Text));
+    assert_eq!(source("data/table.csv"), Some(FileSource::Text));
+    assert_eq!(source("photo.png"), Some(FileSource::Path));
+    assert_eq!(source("scans/Report.PDF"), Some(FileSource::Path));
+    assert_eq!(FileSource::Path.plugin(), "Read From File");
+    assert_eq!(source("Makefile"), None);
+    assert_eq!(source("archive.unknown"), None);
+}
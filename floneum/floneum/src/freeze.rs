@@ -0,0 +1,80 @@
+use floneum_plugin::plugins::main::types::PrimitiveValue;
+
+use crate::Node;
+
+impl Node {
+    /// Keep the current outputs of the node and stop running it until it is unfrozen
+    pub fn freeze(&mut self) {
+        self.frozen_outputs = self
+            .outputs
+            .iter()
+            .map(|output| output.read().value.clone())
+            .collect();
+        self.frozen = true;
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.frozen = false;
+        self.frozen_outputs.clear();
+    }
+
+    pub fn toggle_frozen(&mut self) {
+        if self.frozen {
+            self.unfreeze();
+        } else {
+            self.freeze();
+        }
+    }
+
+    /// The outputs the node was frozen with, if it is frozen
+    pub fn frozen_outputs(&self) -> Option<Vec<Vec<PrimitiveValue>>> {
+        self.frozen.then(|| self.frozen_outputs.clone())
+    }
+
+    /// Set the outputs of the node after a run
+    pub fn set_outputs(&mut self, outputs: &[Vec<PrimitiveValue>]) {
+        self.dirty = false;
+        self.error = None;
+        for (out, current) in outputs.iter().zip(self.outputs.iter()) {
+            current.write_unchecked().value.clone_from(out);
+        }
+    }
+}
+
+#[test]
+fn frozen_nodes_supply_cached_outputs_without_running() {
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use crate::{Point, VisualGraph};
+
+    run_in_dioxus(|| async {
+        let mut graph = VisualGraph::default();
+        let string = test_plugin("string");
+        let frozen = graph.add_node(&string, Point::new(0., 0.)).await.unwrap();
+        let downstream = graph.add_node(&string, Point::new(200., 0.)).await.unwrap();
+        graph.connect(frozen, 0, downstream, 0).unwrap();
+        let text = |value: &str| PrimitiveValue::Text(value.to_string());
+        graph.set_input_value(frozen, 0, text("cached")).unwrap();
+        graph.run_all().await;
+
+        let mut node = graph.inner.read().graph[frozen];
+        node.write().freeze();
+        // The frozen node would pass this value on if it ran again
+        graph.set_input_value(frozen, 0, text("fresh")).unwrap();
+        graph.run_all().await;
+
+        let output = |id| {
+            graph.inner.read().graph[id].read().outputs[0]
+                .read()
+                .value
+                .clone()
+        };
+        assert_eq!(output(frozen), vec![text("cached")]);
+        assert_eq!(output(downstream), vec![text("cached")]);
+
+        // Unfrozen nodes run with their new inputs again
+        node.write().unfreeze();
+        graph.run_all().await;
+        assert_eq!(output(frozen), vec![text("fresh")]);
+        assert_eq!(output(downstream), vec![text("fresh")]);
+    });
+}
@@ -1,19 +1,40 @@
 use std::{collections::HashSet, fmt::Debug};
 
 use dioxus::{
-    html::geometry::{euclid::Point2D, PagePoint},
+    html::geometry::{
+        euclid::{Point2D, Rect},
+        PagePoint,
+    },
     prelude::{SvgAttributes, *},
 };
+use dioxus_free_icons::Icon;
 use floneum_plugin::PluginInstance;
 use petgraph::{
-    stable_graph::StableGraph,
+    stable_graph::{EdgeIndex, NodeIndex, StableGraph},
     visit::{EdgeRef, IntoEdgeReferences, IntoNodeIdentifiers},
 };
 use slab::Slab;
 
 use crate::{
+    accessibility::LiveRegion,
+    autoscroll::{edge_scroll_velocity, EdgeScroll},
+    connect::{DropTarget, HoverValidity},
+    connection::{connection_midpoint, connection_path},
+    crossings::connection_hops,
+    file_drop::create_node_from_file,
+    macro_node::MacroNode,
     node_value::{NodeInput, NodeOutput},
-    Colored, Connection, Edge, Node, Signal,
+    notifications::NotificationList,
+    palette::{Palette, PaletteTarget, SocketFilter},
+    pin::Viewport,
+    recent::RecentlyAddedBar,
+    rehome::RehomeDragInfo,
+    run::RunProgress,
+    search::CanvasSearch,
+    settings::{GraphSettings, RoutingMode},
+    shape::{split_sockets, NodeShape},
+    shortcuts::{handle_shortcut, ShortcutHelp, ShortcutTarget},
+    use_application_state, use_package_manager, Colored, Connection, Edge, Node, Signal,
 };
 
 pub struct VisualGraphInner {
@@ -22,6 +43,19 @@ pub struct VisualGraphInner {
     pub currently_dragging: Option<CurrentlyDragging>,
     pub pan_pos: Point2D<f32, f32>,
     pub zoom: f32,
+    pub rendered_size: Option<Rect<f64, f64>>,
+    /// Nodes that were interacted with, from least to most recent. Nodes later in the list are
+    /// drawn on top
+    pub render_order: Vec<NodeIndex>,
+    pub macros: Slab<MacroNode>,
+    pub edge_scroll: Option<EdgeScroll>,
+    /// How far through the current run of the whole graph is, if a run is active
+    pub run_progress: Option<RunProgress>,
+    /// Set when the current run should stop before the next node starts
+    pub cancel_requested: bool,
+    pub settings: GraphSettings,
+    /// The nodes that were selected with shift click or created by duplicating other nodes
+    pub selection: Vec<NodeIndex>,
 }
 
 impl Default for VisualGraphInner {
@@ -32,15 +66,65 @@ impl Default for VisualGraphInner {
             currently_dragging: None,
             pan_pos: Point2D::new(0.0, 0.0),
             zoom: 1.0,
+            rendered_size: None,
+            render_order: Vec::new(),
+            macros: Slab::default(),
+            edge_scroll: None,
+            run_progress: None,
+            cancel_requested: false,
+            settings: GraphSettings::default(),
+            selection: Vec::new(),
         }
     }
 }
 
 impl VisualGraphInner {
+    /// Transform a point in graph space into screen space with the current pan and zoom
+    pub fn graph_to_screen(&self, pos: Point2D<f32, f32>) -> Point2D<f32, f32> {
+        self.viewport().graph_to_screen(pos)
+    }
+
+    /// Draw the node above every other node
+    pub fn bring_to_front(&mut self, id: NodeIndex) {
+        self.render_order.retain(|&other| other != id);
+        self.render_order.push(id);
+    }
+
+    /// All nodes in the order they should be drawn
+    pub fn nodes_in_render_order(&self) -> Vec<NodeIndex> {
+        let mut nodes: Vec<_> = self
+            .graph
+            .node_identifiers()
+            .filter(|id| !self.render_order.contains(id))
+            .collect();
+        nodes.extend(
+            self.render_order
+                .iter()
+                .copied()
+                .filter(|&id| self.graph.contains_node(id)),
+        );
+        nodes
+    }
+
+    /// Add a node to the selection, or remove it if it is already selected
+    pub fn toggle_selected(&mut self, id: NodeIndex) {
+        if self.selection.contains(&id) {
+            self.selection.retain(|&other| other != id);
+        } else {
+            self.selection.push(id);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.graph.clear();
+        self.render_order.clear();
+        self.selection.clear();
+        self.macros.clear();
         self.connections.clear();
         self.currently_dragging = None;
+        self.edge_scroll = None;
+        self.run_progress = None;
+        self.cancel_requested = false;
         self.pan_pos = Point2D::new(0.0, 0.0);
         self.zoom = 1.0;
     }
@@ -50,6 +134,8 @@ impl VisualGraphInner {
 pub enum CurrentlyDragging {
     Node(NodeDragInfo),
     Connection(CurrentlyDraggingProps),
+    Macro(MacroDragInfo),
+    Rehome(RehomeDragInfo),
 }
 
 impl Debug for CurrentlyDragging {
@@ -57,6 +143,8 @@ impl Debug for CurrentlyDragging {
         match self {
             CurrentlyDragging::Node(_) => write!(f, "Node"),
             CurrentlyDragging::Connection(_) => write!(f, "Connection"),
+            CurrentlyDragging::Macro(_) => write!(f, "Macro"),
+            CurrentlyDragging::Rehome(_) => write!(f, "Rehome"),
         }
     }
 }
@@ -67,6 +155,12 @@ pub struct NodeDragInfo {
     pub node: Signal<Node>,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+pub struct MacroDragInfo {
+    pub element_offset: Point2D<f32, f32>,
+    pub id: usize,
+}
+
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum DraggingIndex {
     Input(crate::edge::Connection),
@@ -79,6 +173,8 @@ pub struct CurrentlyDraggingProps {
     pub index: DraggingIndex,
     pub from_pos: Point2D<f32, f32>,
     pub to: Signal<Point2D<f32, f32>>,
+    /// If the socket under the cursor would accept the connection. Updated as the mouse moves
+    pub hover: Signal<HoverValidity>,
 }
 
 #[derive(Props, Clone, Copy, Default, PartialEq)]
@@ -87,7 +183,7 @@ pub struct VisualGraph {
 }
 
 impl VisualGraph {
-    pub fn create_node(&self, instance: PluginInstance) -> anyhow::Result<()> {
+    pub fn create_node(&self, instance: PluginInstance) -> anyhow::Result<NodeIndex> {
         let position = self.scale_screen_pos(PagePoint::new(0., 0.));
         let mut inner_mut = self.inner;
         let mut inner = inner_mut.write();
@@ -121,6 +217,11 @@ impl VisualGraph {
                 running: false,
                 queued: false,
                 error: None,
+                removing: false,
+                pinned: false,
+                frozen: false,
+                frozen_outputs: Vec::new(),
+                dirty: false,
                 rendered_size: None,
                 id: Default::default(),
                 inputs,
@@ -131,7 +232,7 @@ impl VisualGraph {
         let idx = inner.graph.add_node(node);
         inner.graph[idx].write().id = idx;
 
-        Ok(())
+        Ok(idx)
     }
 
     pub fn scale_screen_pos(&self, pos: PagePoint) -> Point2D<f32, f32> {
@@ -144,29 +245,109 @@ impl VisualGraph {
         pos
     }
 
+    /// The screen position of an input socket. Returns None if the node or input doesn't exist
+    pub fn input_screen_pos(
+        &self,
+        node_id: NodeIndex,
+        index: crate::edge::Connection,
+    ) -> Option<Point2D<f32, f32>> {
+        let inner = self.inner.read();
+        let node = inner.graph.node_weight(node_id)?.read();
+        if index.index >= node.inputs.len() {
+            return None;
+        }
+        Some(inner.graph_to_screen(node.input_pos(index)))
+    }
+
+    /// The screen position of an output socket. Returns None if the node or output doesn't exist
+    pub fn output_screen_pos(&self, node_id: NodeIndex, index: usize) -> Option<Point2D<f32, f32>> {
+        let inner = self.inner.read();
+        let node = inner.graph.node_weight(node_id)?.read();
+        if index >= node.outputs.len() {
+            return None;
+        }
+        Some(inner.graph_to_screen(node.output_pos(index)))
+    }
+
+    /// Pan the viewport so the node is in the center of the canvas
+    pub fn pan_to(&self, id: petgraph::graph::NodeIndex) {
+        let mut inner = self.inner;
+        let mut inner = inner.write();
+        let Some(node) = inner.graph.node_weight(id) else {
+            return;
+        };
+        let center = node.read().center();
+        let viewport_center = inner
+            .rendered_size
+            .map(|size| size.size.to_vector().cast::<f32>() / 2.)
+            .unwrap_or_default();
+        let zoom = inner.zoom;
+        inner.set_viewport(Viewport {
+            pan_pos: Point2D::new(
+                viewport_center.x - center.x * zoom,
+                viewport_center.y - center.y * zoom,
+            ),
+            zoom,
+        });
+    }
+
     pub fn clear_dragging(&mut self) {
-        self.inner.write().currently_dragging = None;
+        let mut inner = self.inner.write();
+        inner.currently_dragging = None;
+        inner.edge_scroll = None;
     }
 
     pub fn update_mouse(&mut self, evt: &MouseData) {
-        let new_pos = self.scale_screen_pos(evt.page_coordinates());
+        self.drag_to(evt.page_coordinates());
+    }
+
+    /// Move whatever is being dragged to the cursor
+    pub fn drag_to(&mut self, cursor: PagePoint) {
+        let new_pos = self.scale_screen_pos(cursor);
         let mut inner = self.inner.write();
         match &mut inner.currently_dragging {
             Some(CurrentlyDragging::Connection(current_graph_dragging)) => {
-                let mut to = current_graph_dragging.to.write();
-                *to = new_pos;
+                let mut drag = *current_graph_dragging;
+                drag.to.set(new_pos);
+                drag.hover.set(inner.hover_validity(&drag, new_pos));
             }
             Some(CurrentlyDragging::Node(current_graph_dragging)) => {
                 let mut node = current_graph_dragging.node.write();
                 node.position.x = new_pos.x - current_graph_dragging.element_offset.x;
                 node.position.y = new_pos.y - current_graph_dragging.element_offset.y;
             }
+            Some(CurrentlyDragging::Macro(current_graph_dragging)) => {
+                let id = current_graph_dragging.id;
+                let position = new_pos - current_graph_dragging.element_offset.to_vector();
+                inner.move_macro(id, position);
+            }
+            Some(CurrentlyDragging::Rehome(current_graph_dragging)) => {
+                let mut to = current_graph_dragging.to;
+                to.set(new_pos);
+            }
             _ => {}
         }
+
+        // Scroll the viewport while something is dragged near the edge of the canvas
+        let velocity = match inner.rendered_size {
+            Some(viewport) if inner.currently_dragging.is_some() => edge_scroll_velocity(
+                viewport.cast::<f32>().cast_unit(),
+                Point2D::new(cursor.x as f32, cursor.y as f32),
+            ),
+            _ => Default::default(),
+        };
+        let scrolling = inner.edge_scroll.is_some();
+        inner.edge_scroll =
+            (velocity != Default::default()).then_some(EdgeScroll { velocity, cursor });
+        if !scrolling && inner.edge_scroll.is_some() {
+            drop(inner);
+            self.start_edge_scroll();
+        }
     }
 
     pub fn start_dragging_node(&mut self, evt: &MouseData, node: Signal<Node>) {
         let mut inner = self.inner.write();
+        inner.bring_to_front(node.read().id);
         inner.currently_dragging = Some(CurrentlyDragging::Node(NodeDragInfo {
             element_offset: evt.element_coordinates().cast().cast_unit(),
             node,
@@ -251,63 +432,31 @@ impl VisualGraph {
         true
     }
 
-    pub fn run_node(&self, mut node: Signal<Node>) {
+    pub fn run_node(&self, node: Signal<Node>) {
         let current_node_id = {
             let current = node.read();
             current.id
         };
-        if self.set_input_nodes(current_node_id) {
-            let inputs = {
-                let mut current_node = node.write();
-                current_node.running = true;
-                current_node.queued = true;
-                current_node
-                    .inputs
-                    .iter()
-                    .map(|input| input.read().value())
-                    .collect()
-            };
-            log::info!(
-                "Running node {:?} with inputs {:?}",
-                current_node_id,
-                inputs
-            );
-
-            let graph = self.inner;
-            spawn(async move {
-                let fut = {
-                    let current_node_write = node.write();
-                    current_node_write.instance.run(inputs)
-                };
-                // Don't hold the write over an await point
-                let result = fut.await;
-                let mut current_node_write = node.write();
-                match result.as_deref() {
-                    Some(Ok(result)) => {
-                        for (out, current) in result.iter().zip(current_node_write.outputs.iter()) {
-                            current.write_unchecked().value.clone_from(out);
-                        }
-
-                        let current_graph = graph.read();
-                        for edge in current_graph
-                            .graph
-                            .edges_directed(current_node_id, petgraph::Direction::Outgoing)
-                        {
-                            let new_node_id = edge.target();
-                            let mut node = current_graph.graph[new_node_id];
-                            node.write().queued = true;
-                        }
-                    }
-                    Some(Err(err)) => {
-                        log::error!("Error running node {:?}: {:?}", current_node_id, err);
-                        current_node_write.error = Some(err.to_string());
+        // Nodes that are waiting on other nodes are queued again when those nodes finish
+        if !self.should_run_node(current_node_id) {
+            return;
+        }
+        let graph = *self;
+        spawn(async move {
+            match graph.execute_node(current_node_id).await {
+                Ok(()) => {
+                    let current_graph = graph.inner.read();
+                    for edge in current_graph
+                        .graph
+                        .edges_directed(current_node_id, petgraph::Direction::Outgoing)
+                    {
+                        let mut node = current_graph.graph[edge.target()];
+                        node.write().queued = true;
                     }
-                    None => {}
                 }
-                current_node_write.running = false;
-                current_node_write.queued = false;
-            });
-        }
+                Err(err) => log::error!("Error running node {:?}: {}", current_node_id, err),
+            }
+        });
     }
 
     pub fn check_connection_validity(
@@ -326,7 +475,7 @@ impl VisualGraph {
         input.compatible(&output)
     }
 
-    pub fn connect(
+    pub fn connect_edge(
         &mut self,
         input_id: petgraph::graph::NodeIndex,
         output_id: petgraph::graph::NodeIndex,
@@ -341,56 +490,73 @@ impl VisualGraph {
             );
             return;
         }
-        let mut current_graph = self.inner.write();
-        // remove any existing connections to this input
-        let mut edges_to_remove = Vec::new();
-        {
-            let input_index = edge.read().end;
-            for edge in current_graph
-                .graph
-                .edges_directed(output_id, petgraph::Direction::Incoming)
-            {
-                if edge.weight().read().end == input_index {
-                    edges_to_remove.push(edge.id());
-                }
-            }
-            for edge in edges_to_remove {
-                current_graph.graph.remove_edge(edge);
-            }
-        }
-        current_graph.graph.add_edge(input_id, output_id, edge);
+        self.inner.write().insert_edge(input_id, output_id, edge);
+    }
+
+    /// Remove a connection and route it through a node instead. Returns false if the node has no
+    /// sockets compatible with both ends of the connection
+    pub fn split_edge(&mut self, edge_id: EdgeIndex, node_id: NodeIndex) -> bool {
+        let current_graph = self.inner.read();
+        let Some((source_id, target_id)) = current_graph.graph.edge_endpoints(edge_id) else {
+            return false;
+        };
+        let edge = current_graph.graph[edge_id].read().clone();
+        let output_ty = current_graph.graph[source_id]
+            .read()
+            .output_type(edge.start);
+        let input_ty = current_graph.graph[target_id].read().input_type(edge.end);
+        let node = NodeShape::new(&current_graph.graph[node_id].read());
+        drop(current_graph);
+        let (Some(output_ty), Some(input_ty)) = (output_ty, input_ty) else {
+            return false;
+        };
+        let Some((input_index, output_index)) = split_sockets(&node, output_ty, input_ty) else {
+            return false;
+        };
+
+        self.inner.write().graph.remove_edge(edge_id);
+        let into_node = crate::edge::Connection {
+            index: input_index,
+            ty: crate::edge::ConnectionType::Single,
+        };
+        self.connect_edge(
+            source_id,
+            node_id,
+            Signal::new(Edge::new(edge.start, into_node)),
+        );
+        self.connect_edge(
+            node_id,
+            target_id,
+            Signal::new(Edge::new(output_index, edge.end)),
+        );
+        true
+    }
+
+    pub fn edge_midpoint(&self, edge_id: EdgeIndex) -> Option<Point2D<f32, f32>> {
+        let current_graph = self.inner.read();
+        let (source_id, target_id) = current_graph.graph.edge_endpoints(edge_id)?;
+        let edge = current_graph.graph[edge_id].read();
+        let start = current_graph.input_anchor(target_id, edge.end);
+        let end = current_graph.output_anchor(source_id, edge.start);
+        Some(connection_midpoint(start, end))
     }
 
     pub(crate) fn finish_connection(
         &mut self,
         node_id: petgraph::graph::NodeIndex,
-        index: DraggingIndex,
+        target: DropTarget,
     ) {
-        let current_graph = self.inner.read();
+        let mut current_graph = self.inner.write();
         if let Some(CurrentlyDragging::Connection(currently_dragging)) =
-            &current_graph.currently_dragging
+            current_graph.currently_dragging
         {
-            let currently_dragging_id = {
-                let from = currently_dragging.from.read();
-                from.id
-            };
-            let ((output_id, output_index), (input_id, input_index)) =
-                match (index, currently_dragging.index) {
-                    (DraggingIndex::Input(input), DraggingIndex::Output(output)) => {
-                        ((node_id, input), (currently_dragging_id, output))
-                    }
-                    (DraggingIndex::Output(output), DraggingIndex::Input(input)) => {
-                        ((currently_dragging_id, input), (node_id, output))
-                    }
-                    _ => return,
-                };
-            drop(current_graph);
-            let edge = Signal::new(Edge::new(input_index, output_index));
-            self.connect(input_id, output_id, edge);
-        } else {
-            drop(current_graph);
+            if let Err(err) =
+                current_graph.complete_connection(&currently_dragging, node_id, target)
+            {
+                tracing::trace!("Connection to {:?} was rejected: {}", node_id, err);
+            }
         }
-        self.inner.write().currently_dragging = None;
+        current_graph.currently_dragging = None;
     }
 
     pub(crate) fn clear(&mut self) {
@@ -405,6 +571,8 @@ pub struct FlowViewProps {
 
 pub fn FlowView(mut props: FlowViewProps) -> Element {
     use_context_provider(|| props.graph);
+    let mut application = use_application_state();
+    let plugins = use_package_manager();
     let mut graph = props.graph.inner;
     let current_graph = graph.read();
     let current_graph_dragging = current_graph.currently_dragging;
@@ -419,6 +587,45 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
     transform_matrix[4] = pan_pos.x;
     transform_matrix[5] = pan_pos.y;
 
+    let stats = current_graph.stats();
+    let routing = current_graph.settings.effective_routing(stats);
+    let hops = if current_graph.settings.draw_crossing_hops(stats) {
+        let connections: Vec<_> = current_graph
+            .graph
+            .edge_references()
+            .filter(|edge_ref| {
+                !current_graph.edge_is_inside_macro(edge_ref.source(), edge_ref.target())
+            })
+            .map(|edge_ref| {
+                let edge = edge_ref.weight().read();
+                (
+                    edge_ref.id(),
+                    current_graph.input_anchor(edge_ref.target(), edge.end),
+                    current_graph.output_anchor(edge_ref.source(), edge.start),
+                )
+            })
+            .collect();
+        connection_hops(&connections, routing)
+    } else {
+        Default::default()
+    };
+    // Connections that are being moved to another output are drawn from their input to the cursor
+    let mut rehoming = Vec::new();
+    if let Some(CurrentlyDragging::Rehome(drag)) = &current_graph_dragging {
+        let from = drag.from.read();
+        let color = from.output_color(drag.output);
+        for edge_ref in current_graph
+            .graph
+            .edges_directed(from.id, petgraph::Direction::Outgoing)
+        {
+            let edge = edge_ref.weight().read();
+            if edge.start == drag.output {
+                let start = current_graph.input_anchor(edge_ref.target(), edge.end);
+                rehoming.push((edge_ref.id(), start, *drag.to.read(), color.clone()));
+            }
+        }
+    }
+
     let transform = format!(
         "matrix({} {} {} {} {} {})",
         transform_matrix[0],
@@ -435,7 +642,23 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
             style: "-webkit-user-select: none; -ms-user-select: none; user-select: none;",
             width: "100%",
             height: "100%",
+            onmounted: move |mount| async move {
+                let size = mount.get_client_rect().await.ok();
+                graph.with_mut(|graph| graph.rendered_size = size);
+            },
+            tabindex: "0",
+            role: "application",
+            aria_label: "Workflow canvas. Tab to a node, move it with the arrow keys, select it with shift enter, and press enter on two sockets to connect them",
             onmousemove: move |evt| props.graph.update_mouse(&evt),
+            onkeydown: move |evt| {
+                handle_shortcut(application, ShortcutTarget::Canvas, &evt);
+            },
+            Palette {}
+            RecentlyAddedBar {}
+            ShortcutHelp {}
+            CanvasSearch {}
+            NotificationList {}
+            LiveRegion {}
             div {
                 position: "absolute",
                 top: "0",
@@ -447,7 +670,7 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
                         let new_zoom = zoom * 1.1;
                         graph
                             .with_mut(|graph| {
-                                graph.zoom = new_zoom;
+                                graph.set_viewport(Viewport { zoom: new_zoom, pan_pos });
                             });
                     },
                     "+"
@@ -458,20 +681,135 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
                         let new_zoom = zoom * 0.9;
                         graph
                             .with_mut(|graph| {
-                                graph.zoom = new_zoom;
+                                graph.set_viewport(Viewport { zoom: new_zoom, pan_pos });
                             });
                     },
                     "-"
                 }
+                button {
+                    class: "m-1",
+                    onclick: move |_| props.graph.tidy(),
+                    "Tidy"
+                }
+                button {
+                    class: "m-1",
+                    disabled: current_graph.selection.is_empty(),
+                    onclick: move |_| {
+                        application.write().collapse_selection();
+                    },
+                    "Collapse"
+                }
+                button {
+                    class: "m-1",
+                    disabled: current_graph.run_progress.is_some(),
+                    onclick: move |_| {
+                        let graph = props.graph;
+                        spawn(async move { graph.run_all().await });
+                    },
+                    "Run all"
+                }
+                label { class: "m-1 text-xs",
+                    input {
+                        r#type: "checkbox",
+                        checked: "{current_graph.settings.routing == RoutingMode::Straight}",
+                        onchange: move |_| {
+                            graph.with_mut(|graph| {
+                                graph.settings.routing = match graph.settings.routing {
+                                    RoutingMode::Curved => RoutingMode::Straight,
+                                    RoutingMode::Straight => RoutingMode::Curved,
+                                };
+                            });
+                        }
+                    }
+                    " Straight connections"
+                }
+                label { class: "m-1 text-xs",
+                    input {
+                        r#type: "checkbox",
+                        checked: "{current_graph.settings.crossing_hops}",
+                        onchange: move |_| {
+                            graph.with_mut(|graph| graph.settings.crossing_hops = !graph.settings.crossing_hops);
+                        }
+                    }
+                    " Crossing hops"
+                }
+                label { class: "m-1 text-xs flex flex-row items-center gap-1",
+                    "Snap distance"
+                    input {
+                        r#type: "range",
+                        min: "8",
+                        max: "64",
+                        value: "{current_graph.settings.snap_distance}",
+                        oninput: move |evt| {
+                            if let Ok(distance) = evt.value().parse() {
+                                graph.write().settings.snap_distance = distance;
+                            }
+                        }
+                    }
+                }
+                label { class: "m-1 text-xs flex flex-row items-center gap-1",
+                    "Socket size"
+                    input {
+                        r#type: "range",
+                        min: "3",
+                        max: "12",
+                        value: "{current_graph.settings.socket_radius}",
+                        oninput: move |evt| {
+                            if let Ok(radius) = evt.value().parse() {
+                                graph.write().settings.socket_radius = radius;
+                            }
+                        }
+                    }
+                }
+                if let Some(run) = current_graph.run_progress {
+                    div {
+                        class: "flex flex-row items-center gap-2 m-1 text-xs",
+                        progress {
+                            max: "{run.total}",
+                            value: "{run.completed}"
+                        }
+                        "{run.completed}/{run.total}"
+                        if current_graph.cancel_requested {
+                            span { "Cancelling…" }
+                        } else {
+                            button {
+                                class: "px-1 border rounded-md",
+                                onclick: move |_| props.graph.cancel_run(),
+                                "Cancel"
+                            }
+                        }
+                    }
+                }
             }
 
-            for id in current_graph.graph.node_identifiers() {
-                Node { key: "{id:?}", node: current_graph.graph[id] }
+            // Nodes are positioned, so they are always drawn above the connections in the svg
+            for id in current_graph.nodes_in_render_order() {
+                if current_graph.macro_containing(id).is_none() {
+                    Node { key: "{id:?}", node: current_graph.graph[id] }
+                }
+            }
+            for (id, _) in current_graph.macros.iter() {
+                MacroNode { key: "macro-{id}", id }
             }
 
             svg {
                 width: "100%",
                 height: "100%",
+                // Files dragged in from the OS become source nodes
+                prevent_default: "ondragover ondrop",
+                ondragover: move |_| {},
+                ondrop: move |evt| {
+                    let plugins = plugins.clone();
+                    async move {
+                        let Some(files) = evt.files() else {
+                            return;
+                        };
+                        let position = props.graph.scale_screen_pos(evt.page_coordinates());
+                        for path in files.files() {
+                            create_node_from_file(application, plugins.clone(), files.clone(), path, position).await;
+                        }
+                    }
+                },
                 onmouseenter: move |data| {
                     if data.held_buttons().is_empty() {
                         props.graph.clear_dragging();
@@ -482,8 +820,33 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
                     drag_start_pos.set(Some(Point2D::new(pos.x as f32, pos.y as f32)));
                     drag_pan_pos.set(Some(pan_pos));
                 },
-                onmouseup: move |_| {
+                onmouseup: move |evt| {
                     drag_start_pos.set(None);
+                    // Dropping a connection from an output in empty space opens the palette with
+                    // plugins that can be connected to it
+                    let dragging = props.graph.inner.read().currently_dragging;
+                    if let Some(CurrentlyDragging::Connection(dragging)) = dragging {
+                        // Connections dropped near a socket snap to it
+                        let point = props.graph.scale_screen_pos(evt.page_coordinates());
+                        let snap = props.graph.inner.read().snap_target(&dragging, point);
+                        if let Some((node, index)) = snap {
+                            props.graph.finish_connection(node, DropTarget::Socket(index));
+                            return;
+                        }
+                        if let DraggingIndex::Output(output) = dragging.index {
+                            let from = dragging.from.read();
+                            let filter = SocketFilter {
+                                input: from.output_type(output),
+                                output: None,
+                            };
+                            let target = PaletteTarget::ConnectFrom {
+                                node: from.id,
+                                output,
+                                position: props.graph.scale_screen_pos(evt.page_coordinates()),
+                            };
+                            application.write().palette.open(target, Some(filter));
+                        }
+                    }
                     props.graph.clear_dragging();
                 },
                 onmousemove: move |evt| {
@@ -496,28 +859,38 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
                         let diff = end_pos - drag_start_pos;
                         graph
                             .with_mut(|graph| {
-                                graph.pan_pos.x = drag_pan_pos.x + diff.x;
-                                graph.pan_pos.y = drag_pan_pos.y + diff.y;
+                                let zoom = graph.zoom;
+                                graph.set_viewport(Viewport { pan_pos: drag_pan_pos + diff, zoom });
                             });
                     }
                     props.graph.update_mouse(&evt);
                 },
 
                 g { transform: "{transform}",
-                    for edge_ref in current_graph.graph.edge_references() {
+                    for edge_ref in current_graph.graph.edge_references().filter(|edge_ref| {
+                        !current_graph.edge_is_inside_macro(edge_ref.source(), edge_ref.target())
+                    }) {
                         NodeConnection {key: "{edge_ref.id():?}",
+                            id: edge_ref.id(),
                             start: current_graph.graph[edge_ref.target()],
                             connection: current_graph.graph[edge_ref.id()],
-                            end: current_graph.graph[edge_ref.source()]
+                            end: current_graph.graph[edge_ref.source()],
+                            routing,
+                            hops: hops.get(&edge_ref.id()).cloned().unwrap_or_default()
                         }
                     }
 
+                    for (id, start_pos, end_pos, color) in rehoming {
+                        Connection { key: "rehome-{id:?}", start_pos, end_pos, color, stale: false, routing }
+                    }
+
                     if let Some(CurrentlyDragging::Connection(current_graph_dragging)) = &current_graph_dragging {
                         CurrentlyDragging {
                             from_pos: current_graph_dragging.from_pos,
                             from: current_graph_dragging.from,
                             index: current_graph_dragging.index,
-                            to: current_graph_dragging.to
+                            to: current_graph_dragging.to,
+                            hover: current_graph_dragging.hover
                         }
                     }
                 }
@@ -528,28 +901,44 @@ pub fn FlowView(mut props: FlowViewProps) -> Element {
 
 #[derive(Clone, Props, PartialEq)]
 pub struct ConnectionProps {
+    id: EdgeIndex,
     start: Signal<Node>,
     connection: Signal<Edge>,
     end: Signal<Node>,
+    routing: RoutingMode,
+    hops: Vec<Point2D<f32, f32>>,
 }
 
 fn CurrentlyDragging(props: CurrentlyDraggingProps) -> Element {
     let start = props.from;
     let current_start = start.read();
     let start_pos = props.from_pos;
-    let color = match props.index {
-        DraggingIndex::Input(index) => current_start.input_color(index),
-        DraggingIndex::Output(index) => current_start.output_color(index),
+    let color = match (props.hover)() {
+        HoverValidity::Valid => "rgb(34, 197, 94)".to_string(),
+        HoverValidity::Invalid => "rgb(239, 68, 68)".to_string(),
+        HoverValidity::Empty => match props.index {
+            DraggingIndex::Input(index) => current_start.input_color(index),
+            DraggingIndex::Output(index) => current_start.output_color(index),
+        },
     };
     let end = props.to;
     let end_pos = end.read();
+    let inner = use_context::<VisualGraph>().inner;
+    let current_graph = inner.read();
+    let routing = current_graph
+        .settings
+        .effective_routing(current_graph.stats());
 
     rsx! {
-        Connection { start_pos, end_pos: *end_pos, color }
+        Connection { start_pos, end_pos: *end_pos, color, stale: false, routing }
     }
 }
 
 fn NodeConnection(props: ConnectionProps) -> Element {
+    let mut application = use_application_state();
+    let mut path_hovered = use_signal(|| false);
+    let mut toolbar_hovered = use_signal(|| false);
+    let id = props.id;
     let start = props.start;
     let connection = props.connection;
     let end = props.end;
@@ -557,14 +946,153 @@ fn NodeConnection(props: ConnectionProps) -> Element {
     let current_connection = connection.read();
     let start_index = current_connection.end;
     let start_node = start.read();
-    let start = start_node.input_pos(start_index);
     let end_index = current_connection.start;
-    let end = end.read().output_pos(end_index);
+    let end_node = end.read();
+    // Connections to nodes inside a macro are drawn to the sockets on the macro
+    let graph = application.read().graph;
+    let (start, end, stale) = {
+        let inner = graph.inner.read();
+        (
+            inner.input_anchor(start_node.id, start_index),
+            inner.output_anchor(end_node.id, end_index),
+            // Connections from nodes with outdated outputs are drawn dashed
+            inner.edge_is_stale(id),
+        )
+    };
 
     let ty = start_node.input_type(start_index).unwrap();
     let color = ty.color();
+    let filter = SocketFilter {
+        input: end_node.output_type(end_index),
+        output: Some(ty),
+    };
+    let path = connection_path(start, end, props.routing);
+    let midpoint = connection_midpoint(start, end);
 
     rsx! {
-        Connection { start_pos: start, end_pos: end, color }
+        Connection {
+            start_pos: start,
+            end_pos: end,
+            color,
+            stale,
+            routing: props.routing,
+            hops: props.hops.clone()
+        }
+        // A wider invisible path that makes the connection easier to hover
+        path {
+            d: "{path}",
+            fill: "none",
+            stroke: "transparent",
+            stroke_width: "12",
+            pointer_events: "stroke",
+            onmouseenter: move |_| path_hovered.set(true),
+            onmouseleave: move |_| path_hovered.set(false)
+        }
+        if path_hovered() || toolbar_hovered() {
+            foreignObject {
+                x: "{midpoint.x - 28.}",
+                y: "{midpoint.y - 12.}",
+                width: "56",
+                height: "24",
+                onmouseenter: move |_| toolbar_hovered.set(true),
+                onmouseleave: move |_| toolbar_hovered.set(false),
+                onmousedown: move |evt| evt.stop_propagation(),
+                div { class: "flex flex-row justify-center gap-1",
+                    button {
+                        class: "p-1 border rounded-md bg-white",
+                        title: "Delete connection",
+                        onclick: move |evt| {
+                            evt.stop_propagation();
+                            let graph = application.read().graph;
+                            let mut inner = graph.inner;
+                            inner.write().remove_connection(id);
+                        },
+                        Icon { width: 12, height: 12, icon: dioxus_free_icons::icons::io_icons::IoTrashOutline }
+                    }
+                    button {
+                        class: "p-1 border rounded-md bg-white",
+                        title: "Insert node",
+                        onclick: move |evt| {
+                            evt.stop_propagation();
+                            application.write().palette.open(PaletteTarget::SplitEdge(id), Some(filter));
+                        },
+                        Icon { width: 12, height: 12, icon: dioxus_free_icons::icons::io_icons::IoAddOutline }
+                    }
+                }
+            }
+        }
     }
 }
+
+#[test]
+fn graph_to_screen_applies_viewport() {
+    let inner = VisualGraphInner {
+        pan_pos: Point2D::new(10., -20.),
+        zoom: 2.,
+        ..Default::default()
+    };
+    assert_eq!(
+        inner.graph_to_screen(Point2D::new(0., 0.)),
+        Point2D::new(10., -20.)
+    );
+    assert_eq!(
+        inner.graph_to_screen(Point2D::new(5., 7.5)),
+        Point2D::new(20., -5.)
+    );
+}
+
+#[test]
+fn toggling_a_node_adds_and_removes_it_from_the_selection() {
+    let mut graph = VisualGraphInner::default();
+    let first = NodeIndex::new(0);
+    let second = NodeIndex::new(1);
+    graph.toggle_selected(first);
+    graph.toggle_selected(second);
+    assert_eq!(graph.selection, [first, second]);
+    graph.toggle_selected(first);
+    assert_eq!(graph.selection, [second]);
+}
+
+#[test]
+fn socket_screen_positions_apply_the_viewport() {
+    use crate::testing::{input, run_in_dioxus, test_plugin};
+    use dioxus::html::geometry::euclid::{Rect, Size2D};
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let id = graph
+            .add_node(&test_plugin("string"), Point2D::new(100., 50.))
+            .await
+            .unwrap();
+        let socket = |x: f64| Some(Rect::new(Point2D::new(x, 20.), Size2D::new(10., 10.)));
+        {
+            let node = graph.inner.read().graph[id];
+            let node = node.read();
+            node.inputs[0].write_unchecked().rendered_size = socket(0.);
+            node.outputs[0].write_unchecked().rendered_size = socket(118.);
+        }
+        {
+            let mut inner = graph.inner;
+            let mut inner = inner.write();
+            inner.pan_pos = Point2D::new(10., -20.);
+            inner.zoom = 2.;
+        }
+
+        // The center of the socket is moved by the node position, then scaled and panned
+        assert_eq!(
+            graph.input_screen_pos(id, input(0)),
+            Some(Point2D::new(220., 130.))
+        );
+        assert_eq!(
+            graph.output_screen_pos(id, 0),
+            Some(Point2D::new(456., 130.))
+        );
+
+        // Sockets the node doesn't have and nodes that don't exist have no position
+        assert_eq!(graph.input_screen_pos(id, input(1)), None);
+        assert_eq!(graph.output_screen_pos(id, 1), None);
+        let missing = NodeIndex::new(id.index() + 1);
+        assert_eq!(graph.input_screen_pos(missing, input(0)), None);
+        assert_eq!(graph.output_screen_pos(missing, 0), None);
+    });
+}
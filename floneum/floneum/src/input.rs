@@ -2,8 +2,12 @@ use dioxus::prelude::*;
 use floneum_plugin::plugins::main::types::ValueType;
 
 use crate::{
-    edge::Connection, graph::CurrentlyDragging, node::NODE_KNOB_SIZE, CurrentlyDraggingProps,
-    DraggingIndex, Node, VisualGraph,
+    accessibility::socket_label,
+    connect::{DropTarget, HoverValidity},
+    edge::Connection,
+    graph::CurrentlyDragging,
+    shortcuts::{handle_shortcut, ShortcutTarget},
+    use_application_state, CurrentlyDraggingProps, DraggingIndex, Node, VisualGraph,
 };
 
 #[component]
@@ -55,18 +59,35 @@ pub fn Input(node: Signal<Node>, index: usize) -> Element {
 
 #[component]
 pub fn InputConnection(node: Signal<Node>, index: Connection) -> Element {
+    let application = use_application_state();
     let current_node = node.read();
     let current_node_id = current_node.id;
     let color = current_node.input_color(index);
     let is_list = current_node.input_is_list(index);
+    let socket_size = application.read().graph.inner.read().settings.socket_size();
+    let label = {
+        let graph = application.read().graph;
+        let connected = graph
+            .inner
+            .read()
+            .input_is_connected(current_node_id, index.index);
+        let name = &current_node.inputs[index.index].read().definition.name;
+        socket_label("Input", name, current_node.input_type(index), connected)
+    };
 
     rsx! {
         button {
-            width: "{NODE_KNOB_SIZE}px",
-            height: "{NODE_KNOB_SIZE}px",
+            width: "{socket_size}px",
+            height: "{socket_size}px",
             border_radius: "50%",
             background_color: "{color}",
             display: "inline-block",
+            aria_label: "{label}",
+            onkeydown: move |evt| {
+                if handle_shortcut(application, ShortcutTarget::Socket(node, DraggingIndex::Input(index)), &evt) {
+                    evt.stop_propagation();
+                }
+            },
             onmounted: move |mount| async move {
                 let size = mount.get_client_rect().await.ok();
                 node.with_mut(|node| {
@@ -86,6 +107,7 @@ pub fn InputConnection(node: Signal<Node>, index: Connection) -> Element {
                         index: DraggingIndex::Input(index),
                         from_pos: graph.scale_screen_pos(evt.page_coordinates()),
                         to: Signal::new(graph.scale_screen_pos(evt.page_coordinates())),
+                        hover: Signal::new(HoverValidity::Empty),
                     }),
                 );
                 graph.inner.write().currently_dragging = new_connection;
@@ -93,7 +115,7 @@ pub fn InputConnection(node: Signal<Node>, index: Connection) -> Element {
             },
             onmouseup: move |evt| {
                 let mut graph: VisualGraph = consume_context();
-                graph.finish_connection(current_node_id, DraggingIndex::Input(index));
+                graph.finish_connection(current_node_id, DropTarget::Socket(DraggingIndex::Input(index)));
                 evt.stop_propagation();
             },
             onmousemove: move |evt| {
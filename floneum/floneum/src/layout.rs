@@ -0,0 +1,114 @@
+use dioxus::html::geometry::euclid::Size2D;
+use dioxus::prelude::*;
+use petgraph::{algo::toposort, graph::NodeIndex, stable_graph::DefaultIx};
+use std::collections::HashMap;
+
+use crate::shape::GraphShape;
+use crate::{Point, VisualGraph};
+
+/// The size used for nodes that haven't been rendered yet
+const DEFAULT_NODE_SIZE: Size2D<f32, f32> = Size2D::new(128., 128.);
+const COLUMN_GAP: f32 = 64.;
+const ROW_GAP: f32 = 32.;
+
+impl VisualGraph {
+    /// Snap the nodes into columns based on how deep they are in the graph while keeping the
+    /// order of the nodes inside each column
+    pub fn tidy(&self) {
+        let graph = self.inner.read();
+        let positions = tidy_positions(&graph.shape());
+        for (id, position) in positions {
+            let mut node = graph.graph[id];
+            node.write().position = position;
+        }
+    }
+}
+
+pub fn tidy_positions(graph: &GraphShape) -> HashMap<NodeIndex<DefaultIx>, Point> {
+    let mut positions = HashMap::new();
+    let Ok(sorted) = toposort(graph, None) else {
+        log::error!("Cannot tidy a graph with cycles");
+        return positions;
+    };
+
+    // Each node goes one column after the deepest node that feeds into it
+    let mut layers: HashMap<NodeIndex<DefaultIx>, usize> = HashMap::new();
+    for &id in &sorted {
+        let layer = graph
+            .neighbors_directed(id, petgraph::Direction::Incoming)
+            .map(|source| layers[&source] + 1)
+            .max()
+            .unwrap_or(0);
+        layers.insert(id, layer);
+    }
+    let column_count = layers.values().max().map_or(0, |max| max + 1);
+    let mut columns = vec![Vec::new(); column_count];
+    for (&id, &layer) in &layers {
+        columns[layer].push(id);
+    }
+
+    let size = |id: NodeIndex<DefaultIx>| {
+        graph[id]
+            .rendered_size
+            .map(|rect| rect.size.cast::<f32>().cast_unit())
+            .unwrap_or(DEFAULT_NODE_SIZE)
+    };
+
+    let Some(mut x) = graph
+        .node_weights()
+        .map(|node| node.position.x)
+        .min_by(f32::total_cmp)
+    else {
+        return positions;
+    };
+    for column in &mut columns {
+        column.sort_by(|a, b| {
+            graph[*a]
+                .position
+                .y
+                .total_cmp(&graph[*b].position.y)
+                .then(a.cmp(b))
+        });
+        let mut y = column
+            .first()
+            .map(|&id| graph[id].position.y)
+            .unwrap_or_default();
+        let mut width: f32 = 0.;
+        for &id in column.iter() {
+            positions.insert(id, Point::new(x, y));
+            let size = size(id);
+            y += size.height + ROW_GAP;
+            width = width.max(size.width);
+        }
+        x += width + COLUMN_GAP;
+    }
+
+    positions
+}
+
+#[test]
+fn chain_is_split_into_columns() {
+    use crate::shape::NodeShape;
+    use crate::testing::{input, text};
+    use crate::Edge;
+
+    let mut graph = GraphShape::default();
+    let mut ids = Vec::new();
+    for (x, y) in [(300., 10.), (0., 200.), (150., 50.)] {
+        let mut node = NodeShape::test(&[text()], &[text()]);
+        node.position = Point::new(x, y);
+        ids.push(graph.add_node(node));
+    }
+    graph.add_edge(ids[0], ids[1], Edge::new(0, input(0)));
+    graph.add_edge(ids[1], ids[2], Edge::new(0, input(0)));
+
+    let positions = tidy_positions(&graph);
+    let xs: Vec<f32> = ids.iter().map(|id| positions[id].x).collect();
+    assert_eq!(xs[0], 0.);
+    assert_eq!(xs[1], 128. + COLUMN_GAP);
+    assert_eq!(xs[2], 2. * (128. + COLUMN_GAP));
+    // Each node is alone in its column, so it keeps its vertical position
+    assert_eq!(positions[&ids[0]].y, 10.);
+    assert_eq!(positions[&ids[1]].y, 200.);
+    assert_eq!(positions[&ids[2]].y, 50.);
+}
@@ -0,0 +1,469 @@
+use dioxus::prelude::*;
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx, visit::EdgeRef};
+
+use crate::connect::{DropTarget, HoverValidity};
+use crate::edge::{Connection, ConnectionType};
+use crate::graph::{CurrentlyDragging, MacroDragInfo};
+use crate::node::stop_dragging;
+use crate::shape::GraphShape;
+use crate::shortcuts::{handle_shortcut, ShortcutTarget};
+use crate::{
+    use_application_state, ApplicationState, CurrentlyDraggingProps, DraggingIndex, Point,
+    VisualGraph, VisualGraphInner,
+};
+
+const MACRO_WIDTH: f32 = 160.;
+const MACRO_HEADER_HEIGHT: f32 = 32.;
+const MACRO_ROW_HEIGHT: f32 = 24.;
+
+/// A group of nodes drawn as a single node.
+///
+/// The members stay in the graph so they keep running like normal. The macro only hides them and
+/// draws every socket that isn't wired up inside the group on the macro itself
+pub struct MacroNode {
+    pub name: String,
+    pub position: Point,
+    pub members: Vec<NodeIndex<DefaultIx>>,
+    /// The member inputs exposed as the inputs of the macro
+    pub inputs: Vec<(NodeIndex<DefaultIx>, Connection)>,
+    /// The member outputs exposed as the outputs of the macro
+    pub outputs: Vec<(NodeIndex<DefaultIx>, usize)>,
+}
+
+impl MacroNode {
+    pub fn input_pos(&self, index: usize) -> Point {
+        Point::new(self.position.x, self.row_y(index))
+    }
+
+    pub fn output_pos(&self, index: usize) -> Point {
+        Point::new(self.position.x + MACRO_WIDTH, self.row_y(index))
+    }
+
+    fn row_y(&self, index: usize) -> f32 {
+        self.position.y + MACRO_HEADER_HEIGHT + MACRO_ROW_HEIGHT * (index as f32 + 0.5)
+    }
+}
+
+/// The sockets of the members that are not connected to another member
+#[derive(Debug, PartialEq)]
+pub struct MacroSockets {
+    pub inputs: Vec<(NodeIndex<DefaultIx>, Connection)>,
+    pub outputs: Vec<(NodeIndex<DefaultIx>, usize)>,
+}
+
+pub fn macro_sockets(graph: &GraphShape, members: &[NodeIndex<DefaultIx>]) -> MacroSockets {
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for &id in members {
+        let node = &graph[id];
+        for index in 0..node.inputs.len() {
+            let mut incoming = graph
+                .edges_directed(id, petgraph::Direction::Incoming)
+                .filter(|edge| edge.weight().end.index == index)
+                .peekable();
+            if incoming.peek().is_none() {
+                inputs.push((
+                    id,
+                    Connection {
+                        index,
+                        ty: ConnectionType::Single,
+                    },
+                ));
+            }
+            // Keep the exact end of every connection from outside the macro so connections to one
+            // element of a list are drawn on the right socket
+            for edge in incoming.filter(|edge| !members.contains(&edge.source())) {
+                let exposed = (id, edge.weight().end);
+                if !inputs.contains(&exposed) {
+                    inputs.push(exposed);
+                }
+            }
+        }
+        for index in 0..node.outputs.len() {
+            let mut targets = graph
+                .edges_directed(id, petgraph::Direction::Outgoing)
+                .filter(|edge| edge.weight().start == index)
+                .map(|edge| edge.target())
+                .peekable();
+            // Outputs that are only used inside the macro are hidden
+            let unused = targets.peek().is_none();
+            if unused || targets.any(|target| !members.contains(&target)) {
+                outputs.push((id, index));
+            }
+        }
+    }
+    MacroSockets { inputs, outputs }
+}
+
+impl VisualGraphInner {
+    /// The macro the node was collapsed into, if any
+    pub fn macro_containing(&self, id: NodeIndex<DefaultIx>) -> Option<usize> {
+        self.macros
+            .iter()
+            .find(|(_, macro_node)| macro_node.members.contains(&id))
+            .map(|(index, _)| index)
+    }
+
+    /// If both ends of an edge are hidden inside the same macro
+    pub fn edge_is_inside_macro(
+        &self,
+        source: NodeIndex<DefaultIx>,
+        target: NodeIndex<DefaultIx>,
+    ) -> bool {
+        match self.macro_containing(source) {
+            Some(macro_id) => self.macro_containing(target) == Some(macro_id),
+            None => false,
+        }
+    }
+
+    /// Where a connection to an input is drawn. This is the socket on the macro if the node is
+    /// collapsed into one
+    pub fn input_anchor(&self, node: NodeIndex<DefaultIx>, index: Connection) -> Point {
+        if let Some(macro_node) = self.macro_containing(node).map(|id| &self.macros[id]) {
+            let exposed = macro_node
+                .inputs
+                .iter()
+                .position(|&(member, input)| member == node && input == index);
+            if let Some(exposed) = exposed {
+                return macro_node.input_pos(exposed);
+            }
+        }
+        self.graph[node].read().input_pos(index)
+    }
+
+    /// Where a connection from an output is drawn. This is the socket on the macro if the node is
+    /// collapsed into one
+    pub fn output_anchor(&self, node: NodeIndex<DefaultIx>, index: usize) -> Point {
+        if let Some(macro_node) = self.macro_containing(node).map(|id| &self.macros[id]) {
+            let exposed = macro_node
+                .outputs
+                .iter()
+                .position(|&(member, output)| member == node && output == index);
+            if let Some(exposed) = exposed {
+                return macro_node.output_pos(exposed);
+            }
+        }
+        self.graph[node].read().output_pos(index)
+    }
+
+    /// Move a macro and every node inside of it
+    pub fn move_macro(&mut self, id: usize, position: Point) {
+        let Some(macro_node) = self.macros.get_mut(id) else {
+            return;
+        };
+        let delta = position - macro_node.position;
+        macro_node.position = position;
+        for &member in &macro_node.members {
+            let mut node = self.graph[member];
+            node.write().position += delta;
+        }
+    }
+
+    /// Forget a node that was removed from the graph. Macros that end up empty are removed
+    pub fn remove_from_macros(&mut self, id: NodeIndex<DefaultIx>) {
+        for (_, macro_node) in self.macros.iter_mut() {
+            macro_node.members.retain(|&member| member != id);
+            macro_node.inputs.retain(|&(member, _)| member != id);
+            macro_node.outputs.retain(|&(member, _)| member != id);
+        }
+        self.macros
+            .retain(|_, macro_node| !macro_node.members.is_empty());
+    }
+}
+
+impl VisualGraph {
+    /// Collapse nodes into a single macro node. Nodes that are already inside a macro are
+    /// skipped. Returns the id of the new macro
+    pub fn collapse_to_macro(&self, selection: &[NodeIndex<DefaultIx>]) -> Option<usize> {
+        let mut inner = self.inner;
+        let mut inner = inner.write();
+        let mut members = Vec::new();
+        for &id in selection {
+            if inner.graph.contains_node(id)
+                && inner.macro_containing(id).is_none()
+                && !members.contains(&id)
+            {
+                members.push(id);
+            }
+        }
+        let position = members
+            .iter()
+            .map(|&id| inner.graph[id].read().position)
+            .reduce(|a, b| a.min(b))?;
+        let MacroSockets { inputs, outputs } = macro_sockets(&inner.shape(), &members);
+        let name = format!("{} nodes", members.len());
+        Some(inner.macros.insert(MacroNode {
+            name,
+            position,
+            members,
+            inputs,
+            outputs,
+        }))
+    }
+
+    /// Put the nodes inside a macro back into the graph. Returns false if the macro doesn't exist
+    pub fn expand_macro(&self, id: usize) -> bool {
+        let mut inner = self.inner;
+        let mut inner = inner.write();
+        if !inner.macros.contains(id) {
+            return false;
+        }
+        let macro_node = inner.macros.remove(id);
+        for member in macro_node.members {
+            inner.bring_to_front(member);
+        }
+        true
+    }
+
+    pub fn start_dragging_macro(&mut self, evt: &MouseData, id: usize) {
+        self.inner.write().currently_dragging = Some(CurrentlyDragging::Macro(MacroDragInfo {
+            element_offset: evt.element_coordinates().cast().cast_unit(),
+            id,
+        }));
+    }
+}
+
+impl ApplicationState {
+    /// Collapse the selected nodes, or the focused node if nothing is selected, into a macro.
+    /// Returns the id of the new macro
+    pub fn collapse_selection(&mut self) -> Option<usize> {
+        let selection = self.selected_nodes();
+        let id = self.graph.collapse_to_macro(&selection)?;
+        let mut inner = self.graph.inner;
+        inner.write().selection.clear();
+        self.currently_focused = None;
+        Some(id)
+    }
+}
+
+#[derive(Props, Clone, Copy, PartialEq)]
+pub struct MacroNodeProps {
+    id: usize,
+}
+
+pub fn MacroNode(props: MacroNodeProps) -> Element {
+    let mut graph: VisualGraph = consume_context();
+    let id = props.id;
+    let inner = graph.inner.read();
+    let macro_node = inner.macros.get(id)?;
+    let name = &macro_node.name;
+    let pos = macro_node.position;
+    let rows = macro_node.inputs.len().max(macro_node.outputs.len());
+    let height = MACRO_HEADER_HEIGHT + MACRO_ROW_HEIGHT * rows as f32;
+    let inputs: Vec<_> = macro_node
+        .inputs
+        .iter()
+        .map(|&(member, input)| (member, DraggingIndex::Input(input)))
+        .collect();
+    let outputs: Vec<_> = macro_node
+        .outputs
+        .iter()
+        .map(|&(member, output)| (member, DraggingIndex::Output(output)))
+        .collect();
+
+    rsx! {
+        div {
+            style: "-webkit-user-select: none; -ms-user-select: none; user-select: none;",
+            class: "flex flex-col rounded-md border shadow-sm bg-white node-enter",
+            position: "absolute",
+            left: "{pos.x}px",
+            top: "{pos.y}px",
+            width: "{MACRO_WIDTH}px",
+            height: "{height}px",
+            onmousedown: move |evt| graph.start_dragging_macro(&evt, id),
+            onmousemove: move |evt| graph.update_mouse(&evt),
+            onmouseup: move |_| graph.clear_dragging(),
+
+            div {
+                class: "flex flex-row flex-shrink-0 items-center justify-between px-2 bg-gray-200 rounded-t-md text-sm font-medium text-black",
+                height: "{MACRO_HEADER_HEIGHT}px",
+                h1 { class: "text-md", "{name}" }
+                button {
+                    class: "px-1 border rounded-md text-xs",
+                    onmousedown: move |evt| evt.stop_propagation(),
+                    onmouseup: |evt| stop_dragging(&evt),
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        graph.expand_macro(id);
+                    },
+                    "Expand"
+                }
+            }
+
+            div { class: "flex flex-row justify-between",
+                div { class: "flex flex-col",
+                    for (member, index) in inputs {
+                        MacroSocket { member, index }
+                    }
+                }
+                div { class: "flex flex-col items-end",
+                    for (member, index) in outputs {
+                        MacroSocket { member, index }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A socket on a macro. Connections dragged to or from it are attached to the member socket it
+/// exposes
+#[component]
+fn MacroSocket(member: NodeIndex<DefaultIx>, index: DraggingIndex) -> Element {
+    let application = use_application_state();
+    let graph: VisualGraph = consume_context();
+    let inner = graph.inner.read();
+    let node = *inner.graph.node_weight(member)?;
+    let current_node = node.read();
+    let (kind, name, color, anchor) = match index {
+        DraggingIndex::Input(input) => (
+            "Input",
+            current_node.inputs[input.index]
+                .read()
+                .definition
+                .name
+                .clone(),
+            current_node.input_color(input),
+            inner.input_anchor(member, input),
+        ),
+        DraggingIndex::Output(output) => (
+            "Output",
+            current_node.outputs[output].read().definition.name.clone(),
+            current_node.output_color(output),
+            inner.output_anchor(member, output),
+        ),
+    };
+    // Outputs are on the right side of the macro, so their socket comes after the name
+    let direction = match index {
+        DraggingIndex::Input(_) => "flex-row",
+        DraggingIndex::Output(_) => "flex-row-reverse",
+    };
+
+    rsx! {
+        div {
+            class: "flex {direction} items-center gap-1 text-xs",
+            height: "{MACRO_ROW_HEIGHT}px",
+            button {
+                class: "w-2 h-2 rounded-full",
+                background_color: "{color}",
+                aria_label: "{kind} {name}",
+                onkeydown: move |evt| {
+                    if handle_shortcut(application, ShortcutTarget::Socket(node, index), &evt) {
+                        evt.stop_propagation();
+                    }
+                },
+                onmousedown: move |evt| {
+                    evt.stop_propagation();
+                    let mut graph: VisualGraph = consume_context();
+                    graph.inner.write().currently_dragging = Some(
+                        CurrentlyDragging::Connection(CurrentlyDraggingProps {
+                            from: node,
+                            from_pos: anchor,
+                            index,
+                            to: Signal::new(anchor),
+                            hover: Signal::new(HoverValidity::Empty),
+                        }),
+                    );
+                },
+                onmouseup: move |evt| {
+                    evt.stop_propagation();
+                    let mut graph: VisualGraph = consume_context();
+                    graph.finish_connection(member, DropTarget::Socket(index));
+                },
+            }
+            "{name}"
+        }
+    }
+}
+
+#[test]
+fn boundary_sockets_are_exposed() {
+    use crate::shape::NodeShape;
+    use crate::testing::{input, text};
+    use crate::Edge;
+
+    let mut graph = GraphShape::default();
+    let outside = graph.add_node(NodeShape::test(&[], &[text()]));
+    let first = graph.add_node(NodeShape::test(&[text(), text()], &[text()]));
+    let second = graph.add_node(NodeShape::test(&[text()], &[text(), text()]));
+    let after = graph.add_node(NodeShape::test(&[text()], &[]));
+    graph.add_edge(outside, first, Edge::new(0, input(0)));
+    graph.add_edge(first, second, Edge::new(0, input(0)));
+    graph.add_edge(second, after, Edge::new(1, input(0)));
+
+    let sockets = macro_sockets(&graph, &[first, second]);
+    // The input fed from outside the macro and the unconnected input are exposed, but the input
+    // fed by another member is not
+    assert_eq!(sockets.inputs, vec![(first, input(0)), (first, input(1))]);
+    // The output only used by the second member is hidden
+    assert_eq!(sockets.outputs, vec![(second, 0), (second, 1)]);
+}
+
+#[test]
+fn element_connections_keep_their_element() {
+    use crate::shape::NodeShape;
+    use crate::testing::text;
+    use crate::Edge;
+    use floneum_plugin::plugins::main::types::{PrimitiveValueType, ValueType};
+
+    let texts = ValueType::Many(PrimitiveValueType::Text);
+    let mut graph = GraphShape::default();
+    let outside = graph.add_node(NodeShape::test(&[], &[text()]));
+    let list = graph.add_node(NodeShape::test(&[texts], &[]));
+    let element = |index| Connection {
+        index: 0,
+        ty: ConnectionType::Element(index),
+    };
+    graph.add_edge(outside, list, Edge::new(0, element(0)));
+    graph.add_edge(outside, list, Edge::new(0, element(1)));
+
+    let sockets = macro_sockets(&graph, &[list]);
+    assert_eq!(sockets.inputs, vec![(list, element(0)), (list, element(1))]);
+}
+
+#[test]
+fn expanding_a_collapsed_macro_restores_the_graph() {
+    use crate::testing::{input, run_in_dioxus, test_plugin};
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let string = test_plugin("string");
+        let first = graph.add_node(&string, Point::new(0., 0.)).await.unwrap();
+        let second = graph.add_node(&string, Point::new(200., 0.)).await.unwrap();
+        let third = graph.add_node(&string, Point::new(400., 0.)).await.unwrap();
+        let mut inner = graph.inner;
+        inner
+            .write()
+            .insert_edge(first, second, Signal::new(crate::Edge::new(0, input(0))));
+        inner
+            .write()
+            .insert_edge(second, third, Signal::new(crate::Edge::new(0, input(0))));
+        let edges = || {
+            let inner = graph.inner.read();
+            inner
+                .graph
+                .edge_references()
+                .map(|edge| (edge.source(), edge.target(), edge.weight().read().clone()))
+                .collect::<Vec<_>>()
+        };
+        let stats = inner.read().stats();
+        let original_edges = edges();
+
+        let id = graph.collapse_to_macro(&[first, second]).unwrap();
+        {
+            let inner = graph.inner.read();
+            assert_eq!(inner.macro_containing(first), Some(id));
+            assert_eq!(inner.macro_containing(second), Some(id));
+            assert_eq!(inner.macros[id].inputs, vec![(first, input(0))]);
+            assert_eq!(inner.macros[id].outputs, vec![(second, 0)]);
+            // The macro and the node after it, joined by the connection that leaves the macro
+            assert_eq!(inner.stats().nodes, 2);
+            assert_eq!(inner.stats().edges, 1);
+        }
+
+        assert!(graph.expand_macro(id));
+        assert!(!graph.expand_macro(id));
+        assert_eq!(inner.read().stats(), stats);
+        assert_eq!(edges(), original_edges);
+    });
+}
@@ -3,20 +3,32 @@
 
 use anyhow::Result;
 use dioxus::{html::geometry::euclid::Point2D, prelude::*};
-use floneum_plugin::{Plugin, ResourceStorage};
-use floneumite::FloneumPackageIndex;
+use floneum_plugin::plugins::main::types::Definition;
+use floneum_plugin::{load_plugin_from_source, Plugin, ResourceStorage};
+use floneumite::{FloneumPackageIndex, PackageIndexEntry};
 
 use petgraph::stable_graph::{DefaultIx, NodeIndex};
 
 use std::{collections::HashMap, fs::File, rc::Rc};
 
+mod accessibility;
 mod node;
 pub use node::Node;
+mod palette;
+mod pin;
+use palette::PaletteState;
+mod duplicate;
 mod edge;
+mod file_drop;
+mod freeze;
 pub use edge::Edge;
 mod graph;
 pub use graph::{CurrentlyDraggingProps, DraggingIndex, FlowView, VisualGraph, VisualGraphInner};
+mod autoscroll;
+mod builder;
+mod connect;
 mod connection;
+mod crossings;
 pub use connection::Connection;
 mod plugin_search;
 mod sidebar;
@@ -24,12 +36,31 @@ use sidebar::Sidebar;
 mod current_node;
 use current_node::{CurrentNodeInfo, FocusedNodeInfo};
 mod node_value;
+mod notifications;
+use notifications::Notifications;
 // mod share;
 mod theme;
 use crate::window::{make_config, use_apply_menu_event};
 pub use node_value::*;
 mod input;
+mod layout;
+mod macro_node;
 mod output;
+mod recent;
+use recent::RecentlyAdded;
+mod rehome;
+mod run;
+mod schema;
+mod search;
+use search::CanvasSearchState;
+mod settings;
+mod shape;
+mod shortcuts;
+#[cfg(test)]
+mod testing;
+mod undo;
+use undo::UndoHistory;
+mod validate;
 mod window;
 
 const SAVE_NAME: &str = "workflow.json";
@@ -80,16 +111,23 @@ pub struct ApplicationState {
     currently_focused: Option<FocusedNodeInfo>,
     resource_storage: ResourceStorage,
     plugins: HashMap<String, Plugin>,
+    palette: PaletteState,
+    shortcut_help_open: bool,
+    canvas_search: CanvasSearchState,
+    notifications: Notifications,
+    recently_added: RecentlyAdded,
+    undo_history: UndoHistory,
     // last_save_id: Option<share::StorageId<ApplicationState>>,
 }
 
 impl ApplicationState {
-    async fn insert_plugin(&mut self, name: &str) -> Result<()> {
+    async fn insert_plugin(&mut self, name: &str) -> Result<NodeIndex<DefaultIx>> {
         match self.get_plugin(name) {
             Some(plugin) => {
                 let instance = plugin.instance().await?;
-                self.graph.create_node(instance)?;
-                Ok(())
+                let id = self.graph.create_node(instance)?;
+                self.recently_added.push(name);
+                Ok(id)
             }
             None => Err(anyhow::anyhow!("Plugin not found")),
         }
@@ -106,8 +144,34 @@ impl ApplicationState {
         self.plugins.get(name)
     }
 
+    /// The definition of a plugin that is already loaded. This doesn't compile the plugin
+    fn plugin_definition(&self, name: &str) -> Option<&Definition> {
+        self.get_plugin(name)?.loaded_definition()
+    }
+
+    /// Focus a node, draw it above the other nodes and pan it into view
+    fn focus_node(&mut self, id: NodeIndex<DefaultIx>) {
+        let Some(node) = self.graph.inner.read().graph.node_weight(id).copied() else {
+            return;
+        };
+        self.currently_focused = Some(FocusedNodeInfo {
+            node,
+            active_example_index: None,
+        });
+        let mut inner = self.graph.inner;
+        inner.write().bring_to_front(id);
+        self.graph.pan_to(id);
+    }
+
     fn remove(&mut self, node: NodeIndex<DefaultIx>) {
-        self.graph.inner.write().graph.remove_node(node);
+        {
+            let mut graph = self.graph.inner.write();
+            graph.graph.remove_node(node);
+            graph.render_order.retain(|&other| other != node);
+            graph.selection.retain(|&other| other != node);
+            graph.remove_from_macros(node);
+        }
+        self.undo_history.forget(node);
         if let Some(focused) = &self.currently_focused {
             if focused.node.read().id == node {
                 self.currently_focused = None;
@@ -122,6 +186,31 @@ impl ApplicationState {
     }
 }
 
+/// Load a plugin from the package index if it hasn't been loaded yet and add a node running it
+async fn insert_plugin_from_entry(
+    mut application: Signal<ApplicationState>,
+    entry: PackageIndexEntry,
+) -> Result<NodeIndex<DefaultIx>> {
+    let plugin = {
+        let read = application.read();
+        load_plugin_from_source(entry, read.resource_storage.clone())
+    };
+    let name = plugin.name().await?;
+    // Reuse the plugin if it is already loaded. The plugin is cloned so the application state
+    // isn't borrowed while the instance is created
+    let plugin = application
+        .write()
+        .plugins
+        .entry(name.clone())
+        .or_insert(plugin)
+        .clone();
+    let instance = plugin.instance().await?;
+    let mut application = application.write();
+    let id = application.graph.create_node(instance)?;
+    application.recently_added.push(&name);
+    Ok(id)
+}
+
 impl PartialEq for ApplicationState {
     fn eq(&self, other: &Self) -> bool {
         self.graph == other.graph
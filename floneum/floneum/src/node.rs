@@ -3,19 +3,25 @@ use dioxus::html::geometry::euclid::Rect;
 use dioxus::html::geometry::euclid::Vector2D;
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
-use floneum_plugin::plugins::main::types::ValueType;
+use floneum_plugin::plugins::main::types::{PrimitiveValue, ValueType};
 use floneum_plugin::PluginInstance;
 use floneumite::Category;
 use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+use std::time::Duration;
 
+use crate::accessibility::{node_label, NodeStatus};
+use crate::connect::DropTarget;
 use crate::edge::{Connection, ConnectionType};
+use crate::graph::CurrentlyDragging;
 use crate::input::Input;
 use crate::node_value::{NodeInput, NodeOutput};
 use crate::output::Output;
+use crate::shortcuts::{handle_shortcut, ShortcutTarget};
 use crate::{theme, use_application_state, Colored};
 use crate::{Point, VisualGraph};
 
-pub const NODE_KNOB_SIZE: f64 = 10.;
+/// How long the fade out animation runs before a removed node is taken out of the graph
+pub const NODE_EXIT_DURATION: Duration = Duration::from_millis(150);
 
 pub fn stop_dragging<T>(evt: &Event<T>) {
     evt.stop_propagation();
@@ -32,6 +38,18 @@ pub struct Node {
     pub queued: bool,
     // #[serde(skip)]
     pub error: Option<String>,
+    // #[serde(skip)]
+    pub removing: bool,
+    /// If an input changed since the node last ran successfully
+    // #[serde(skip)]
+    pub dirty: bool,
+    /// Pinned nodes stay at the same place on screen while the rest of the graph is panned and
+    /// zoomed
+    // #[serde(default)]
+    pub pinned: bool,
+    /// Frozen nodes are not run again. The outputs they were frozen with are used instead
+    pub frozen: bool,
+    pub frozen_outputs: Vec<Vec<PrimitiveValue>>,
     pub id: NodeIndex<DefaultIx>,
     pub position: Point,
     pub rendered_size: Option<Rect<f64, f64>>,
@@ -93,25 +111,88 @@ impl Node {
         }
     }
 
+    /// If the outputs of the node are out of date or missing because the node failed
+    pub fn is_stale(&self) -> bool {
+        self.dirty || self.error.is_some()
+    }
+
     pub fn help_text(&self) -> String {
         self.instance.metadata().description.to_string()
     }
 
+    pub fn geometry(&self) -> NodeGeometry {
+        NodeGeometry {
+            position: self.position,
+            rendered_size: self.rendered_size,
+        }
+    }
+
+    /// The position of the input socket in graph space. Use [`VisualGraph::input_screen_pos`] to
+    /// get the position with the viewport applied
     pub fn input_pos(&self, index: Connection) -> Point {
         let input = self.inputs[index.index];
-        let mut pos = input.read().rendered_size.unwrap_or_default().center();
-        pos += self.offset();
-        Point::new(pos.x as f32, pos.y as f32)
+        let socket = input.read().rendered_size;
+        self.geometry().socket_pos(socket)
     }
 
+    /// The position of the output socket in graph space. Use [`VisualGraph::output_screen_pos`]
+    /// to get the position with the viewport applied
     pub fn output_pos(&self, index: usize) -> Point {
         let output = self.outputs[index];
-        let mut pos = output.read().rendered_size.unwrap_or_default().center();
+        let socket = output.read().rendered_size;
+        self.geometry().socket_pos(socket)
+    }
+
+    pub fn center(&self) -> Point {
+        self.geometry().center()
+    }
+
+    pub fn bounds(&self) -> Rect<f32, f32> {
+        self.geometry().bounds()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.geometry().contains(point)
+    }
+
+    pub(crate) fn offset(&self) -> Vector2D<f64, f64> {
+        self.geometry().offset()
+    }
+}
+
+/// The parts of a node that decide where it is drawn. The rendered sizes of the node and its
+/// sockets are relative to the position of the node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeGeometry {
+    pub position: Point,
+    pub rendered_size: Option<Rect<f64, f64>>,
+}
+
+impl NodeGeometry {
+    /// The box the node covers in graph space. This is empty until the node is rendered
+    pub fn bounds(&self) -> Rect<f32, f32> {
+        let mut bounds = self.rendered_size.unwrap_or_default();
+        bounds.origin += self.offset();
+        bounds.cast::<f32>().cast_unit()
+    }
+
+    pub fn center(&self) -> Point {
+        self.bounds().center()
+    }
+
+    /// If the point is inside the node. Points on the right and bottom edges are outside
+    pub fn contains(&self, point: Point) -> bool {
+        self.bounds().contains(point)
+    }
+
+    /// The center of a socket with the given rendered size in graph space
+    pub fn socket_pos(&self, socket: Option<Rect<f64, f64>>) -> Point {
+        let mut pos = socket.unwrap_or_default().center();
         pos += self.offset();
         Point::new(pos.x as f32, pos.y as f32)
     }
 
-    pub(crate) fn offset(&self) -> Vector2D<f64, f64> {
+    fn offset(&self) -> Vector2D<f64, f64> {
         self.position.to_vector().cast().cast_unit()
     }
 }
@@ -131,22 +212,66 @@ pub fn Node(props: NodeProps) -> Element {
         None => Category::Other,
     };
     let color = theme::category_bg_color(category);
-    let pos = current_node.position;
+    let pos = application
+        .read()
+        .graph
+        .inner
+        .read()
+        .drawn_position(&current_node);
+    let pinned_class = if current_node.pinned { "z-10" } else { "" };
+    let selected = application
+        .read()
+        .graph
+        .inner
+        .read()
+        .selection
+        .contains(&current_node.id);
+    let selected_class = if selected { "ring-2 ring-blue-300" } else { "" };
+    let socket_size = application.read().graph.inner.read().settings.socket_size();
     let focused = application.read().currently_focused.map(|n| n.node) == Some(node);
+    let search_class = {
+        let application = application.read();
+        let search = &application.canvas_search;
+        if !search.is_active() {
+            ""
+        } else if search.matches.contains(&current_node.id) {
+            "ring-2 ring-yellow-400"
+        } else {
+            "opacity-25"
+        }
+    };
     let focused_class = if focused {
-        "border-2 border-blue-500"
+        "border-2 border-blue-500 shadow-lg"
     } else {
-        "border"
+        "border shadow-sm"
+    };
+    let label = {
+        let graph = application.read().graph;
+        let connections = graph.inner.read().connection_summary(current_node.id);
+        node_label(name, NodeStatus::of(&current_node), connections)
+    };
+    let animation_class = if current_node.removing {
+        "node-exit"
+    } else {
+        "node-enter"
     };
 
     rsx! {
         // center UI/Configuration
         div {
             style: "-webkit-user-select: none; -ms-user-select: none; user-select: none;",
-            class: "shadow-sm resize w-32 h-32 flex flex-col rounded-md {focused_class}",
+            class: "resize w-32 h-32 flex flex-col rounded-md {focused_class} {animation_class} {search_class} {pinned_class} {selected_class}",
             position: "absolute",
             left: "{pos.x}px",
             top: "{pos.y}px",
+            role: "group",
+            aria_label: "{label}",
+            tabindex: "0",
+            onkeydown: move |evt| {
+                if handle_shortcut(application, ShortcutTarget::Node(node), &evt) {
+                    evt.stop_propagation();
+                }
+            },
             onmounted: move |mount| async move {
                 let size = mount.get_client_rect().await.ok();
                 node.with_mut(|node| {
@@ -165,9 +290,25 @@ pub fn Node(props: NodeProps) -> Element {
                 let mut graph: VisualGraph = consume_context();
                 graph.update_mouse(&evt);
             },
-            onmouseup: move |_| {
+            onmouseup: move |evt| {
                 let mut graph: VisualGraph = consume_context();
+                let dragging_connection = matches!(
+                    graph.inner.read().currently_dragging,
+                    Some(CurrentlyDragging::Connection(_))
+                );
+                if dragging_connection {
+                    let point = graph.scale_screen_pos(evt.page_coordinates());
+                    graph.finish_connection(props.node.read().id, DropTarget::Point(point));
+                    return;
+                }
                 graph.clear_dragging();
+                let id = props.node.read().id;
+                // Shift click adds the node to the selection or removes it
+                if evt.modifiers().contains(Modifiers::SHIFT) {
+                    graph.inner.write().toggle_selected(id);
+                    return;
+                }
+                graph.inner.write().selection.clear();
                 let mut application = application.write();
                 match &application.currently_focused {
                     Some(
@@ -180,6 +321,7 @@ pub fn Node(props: NodeProps) -> Element {
                             node: props.node,
                             active_example_index: None,
                         });
+                        graph.inner.write().bring_to_front(props.node.read().id);
                     }
                 }
             },
@@ -187,6 +329,15 @@ pub fn Node(props: NodeProps) -> Element {
             // The node name
             div { class: "flex w-full h-8 flex-shrink-0 items-center justify-center {color} rounded-t-md text-sm font-medium text-black",
                 h1 { class: "text-md", "{name}" }
+                if current_node.frozen {
+                    span { class: "ml-1", title: "Frozen", aria_label: "Frozen",
+                        Icon {
+                            width: 12,
+                            height: 12,
+                            icon: dioxus_free_icons::icons::io_icons::IoSnow
+                        }
+                    }
+                }
             }
 
             // Everything else horizontally spread underneath it
@@ -195,7 +346,8 @@ pub fn Node(props: NodeProps) -> Element {
                 // inputs
                 div { class: "flex flex-col justify-around h-full",
                     for index in 0..current_node.inputs.len() {
-                        Input { node, index }
+                        // Remount the sockets when their size changes so their positions are measured again
+                        Input { key: "{index}-{socket_size}", node, index }
                     }
                 }
 
@@ -204,7 +356,7 @@ pub fn Node(props: NodeProps) -> Element {
                 div { class: "flex flex-col justify-around h-full",
                     // outputs
                     for index in 0..current_node.outputs.len() {
-                        Output { node, index }
+                        Output { key: "{index}-{socket_size}", node, index }
                     }
                 }
             }
@@ -222,6 +374,18 @@ fn CenterNodeUI(mut node: Signal<Node>) -> Element {
         application.graph.run_node(node);
     }
     let current_node = node.read();
+    let pinned = current_node.pinned;
+    let (pin_label, pin_class) = if pinned {
+        ("Unpin node", "bg-blue-100")
+    } else {
+        ("Pin node", "")
+    };
+    let frozen = current_node.frozen;
+    let (freeze_label, freeze_class) = if frozen {
+        ("Unfreeze node", "bg-blue-100")
+    } else {
+        ("Freeze node", "")
+    };
 
     rsx! {
         div {
@@ -230,9 +394,23 @@ fn CenterNodeUI(mut node: Signal<Node>) -> Element {
             div {
                 button {
                     class: "p-2 border top-0 right-0",
+                    aria_label: "Delete node",
                     onclick: move |evt| {
                         evt.stop_propagation();
-                        application.write().remove(node.read().id);
+                        let id = {
+                            let mut node = node.write();
+                            node.removing = true;
+                            node.id
+                        };
+                        // Let the fade out animation finish before removing the node from the graph
+                        spawn(async move {
+                            tokio::time::sleep(NODE_EXIT_DURATION).await;
+                            let graph = application.read().graph;
+                            let still_in_graph = graph.inner.read().graph.node_weight(id) == Some(&node);
+                            if still_in_graph {
+                                application.write().remove(id);
+                            }
+                        });
                     },
                     onmousedown: move |evt| {
                         evt.stop_propagation();
@@ -247,11 +425,58 @@ fn CenterNodeUI(mut node: Signal<Node>) -> Element {
                         icon: dioxus_free_icons::icons::io_icons::IoTrashOutline
                     }
                 }
+                button {
+                    class: "p-2 border {pin_class}",
+                    title: "{pin_label}",
+                    aria_label: "{pin_label}",
+                    aria_pressed: "{pinned}",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        let id = node.read().id;
+                        let mut inner = application.read().graph.inner;
+                        inner.write().toggle_pinned(id);
+                    },
+                    onmousedown: move |evt| {
+                        evt.stop_propagation();
+                    },
+                    onmousemove: |evt| {
+                        evt.stop_propagation();
+                    },
+                    onmouseup: |evt| stop_dragging(&evt),
+                    Icon {
+                        width: 15,
+                        height: 15,
+                        icon: dioxus_free_icons::icons::io_icons::IoPinOutline
+                    }
+                }
+                button {
+                    class: "p-2 border {freeze_class}",
+                    title: "{freeze_label}",
+                    aria_label: "{freeze_label}",
+                    aria_pressed: "{frozen}",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        node.with_mut(|node| node.toggle_frozen());
+                    },
+                    onmousedown: move |evt| {
+                        evt.stop_propagation();
+                    },
+                    onmousemove: |evt| {
+                        evt.stop_propagation();
+                    },
+                    onmouseup: |evt| stop_dragging(&evt),
+                    Icon {
+                        width: 15,
+                        height: 15,
+                        icon: dioxus_free_icons::icons::io_icons::IoSnowOutline
+                    }
+                }
                 if current_node.running {
-                    "Loading..."
+                    span { aria_busy: "true", "Loading..." }
                 } else {
                     button {
                         class: "p-1 border rounded-md ",
+                        aria_label: "Run node",
                         onclick: move |evt| {
                             evt.stop_propagation();
                             node.write().queued = true;
@@ -266,7 +491,7 @@ fn CenterNodeUI(mut node: Signal<Node>) -> Element {
                         "Run"
                     }
                 }
-                div { color: "red",
+                div { color: "red", role: "alert",
                     if let Some(error) = &current_node.error {
                         p { "{error}" }
                     }
@@ -275,3 +500,70 @@ fn CenterNodeUI(mut node: Signal<Node>) -> Element {
         }
     }
 }
+
+#[cfg(test)]
+fn test_rect(x: f64, y: f64, width: f64, height: f64) -> Rect<f64, f64> {
+    Rect::new(
+        dioxus::html::geometry::euclid::Point2D::new(x, y),
+        dioxus::html::geometry::euclid::Size2D::new(width, height),
+    )
+}
+
+#[test]
+fn geometry_of_a_rendered_node() {
+    let geometry = NodeGeometry {
+        position: Point::new(100., 50.),
+        rendered_size: Some(test_rect(0., 0., 128., 96.)),
+    };
+
+    assert_eq!(
+        geometry.bounds(),
+        test_rect(100., 50., 128., 96.).cast().cast_unit()
+    );
+    assert_eq!(geometry.center(), Point::new(164., 98.));
+    assert!(geometry.contains(Point::new(100., 50.)));
+    assert!(geometry.contains(Point::new(164., 98.)));
+    assert!(!geometry.contains(Point::new(228., 146.)));
+    assert!(!geometry.contains(Point::new(99., 98.)));
+
+    // Two inputs on the left edge and three outputs on the right edge
+    let inputs = [test_rect(-5., 27., 10., 10.), test_rect(-5., 59., 10., 10.)];
+    let outputs = [
+        test_rect(123., 19., 10., 10.),
+        test_rect(123., 43., 10., 10.),
+        test_rect(123., 67., 10., 10.),
+    ];
+    let input_positions: Vec<_> = inputs
+        .iter()
+        .map(|&socket| geometry.socket_pos(Some(socket)))
+        .collect();
+    assert_eq!(
+        input_positions,
+        [Point::new(100., 82.), Point::new(100., 114.)]
+    );
+    let output_positions: Vec<_> = outputs
+        .iter()
+        .map(|&socket| geometry.socket_pos(Some(socket)))
+        .collect();
+    assert_eq!(
+        output_positions,
+        [
+            Point::new(228., 74.),
+            Point::new(228., 98.),
+            Point::new(228., 122.)
+        ]
+    );
+}
+
+#[test]
+fn geometry_before_rendering() {
+    let geometry = NodeGeometry {
+        position: Point::new(10., 20.),
+        rendered_size: None,
+    };
+    // Nodes that haven't been measured yet have no size, so they can't contain any point
+    assert_eq!(geometry.center(), Point::new(10., 20.));
+    assert!(geometry.bounds().is_empty());
+    assert!(!geometry.contains(Point::new(10., 20.)));
+    assert_eq!(geometry.socket_pos(None), Point::new(10., 20.));
+}
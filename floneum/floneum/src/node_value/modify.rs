@@ -3,30 +3,85 @@ use crate::node_value::model_type_from_str;
 use crate::node_value::Named;
 use crate::node_value::Variants;
 use crate::show_primitive_value;
-use crate::{node_value::NodeInput, Signal};
+use crate::{node_value::NodeInput, Node, Signal};
 use dioxus::prelude::*;
 use floneum_plugin::plugins::main::types::*;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+/// Change the value of an input. The outputs of the node that owns the input are out of date, so
+/// the node is queued to run again with the new value
+pub fn edit_input(
+    input: Signal<NodeInput>,
+    owner: Signal<Node>,
+    edit: impl FnOnce(&mut Vec<Vec<PrimitiveValue>>),
+) {
+    edit(&mut input.write_unchecked().value);
+    let mut owner = owner.write_unchecked();
+    owner.dirty = true;
+    owner.queued = true;
+}
+
+/// The editor shown for a value
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputWidget {
+    /// A single line text field for inputs that accept any type
+    TextField(String),
+    /// A multiline box for text inputs
+    TextArea(String),
+    FilePicker(String),
+    FolderPicker(String),
+    NumberField(i64),
+    FloatField(f64),
+    ModelTypeDropdown(ModelType),
+    EmbeddingModelTypeDropdown(EmbeddingModelType),
+    Checkbox(bool),
+    /// Values that can't be typed in are only shown
+    ReadOnly(PrimitiveValue),
+}
+
+impl InputWidget {
+    /// Pick the editor for a value from the type the plugin declared for the input
+    pub fn new(value: PrimitiveValue, ty: PrimitiveValueType) -> Self {
+        match value {
+            PrimitiveValue::Text(text) if ty != PrimitiveValueType::Text => Self::TextField(text),
+            PrimitiveValue::Text(text) => Self::TextArea(text),
+            PrimitiveValue::File(file) => Self::FilePicker(file),
+            PrimitiveValue::Folder(folder) => Self::FolderPicker(folder),
+            PrimitiveValue::Number(number) => Self::NumberField(number),
+            PrimitiveValue::Float(float) => Self::FloatField(float),
+            PrimitiveValue::ModelType(ty) => Self::ModelTypeDropdown(ty),
+            PrimitiveValue::EmbeddingModelType(ty) => Self::EmbeddingModelTypeDropdown(ty),
+            PrimitiveValue::Boolean(value) => Self::Checkbox(value),
+            PrimitiveValue::Embedding(_)
+            | PrimitiveValue::Model(_)
+            | PrimitiveValue::EmbeddingModel(_)
+            | PrimitiveValue::Database(_)
+            | PrimitiveValue::Page(_)
+            | PrimitiveValue::Node(_) => Self::ReadOnly(value),
+        }
+    }
+}
+
 #[component]
-pub fn ModifyInput(node: Signal<NodeInput>) -> Element {
+pub fn ModifyInput(node: Signal<NodeInput>, owner: Signal<Node>) -> Element {
     let current_value = node.read();
     let name = &current_value.definition.name;
     let values = current_value.value();
-    match &current_value.definition.ty {
-        ValueType::Single(_) => rsx! {
+    match current_value.definition.ty {
+        ValueType::Single(ty) => rsx! {
             div { class: "flex flex-col",
                 "{name}: "
                 ModifySingleValue {
                     value: values[0].clone(),
+                    ty,
                     set_value: Rc::new(move |value| {
-                        node.write_unchecked().value = vec![vec![value]];
+                        edit_input(node, owner, |current| *current = vec![vec![value]]);
                     })
                 }
             }
         },
-        ValueType::Many(_) => {
+        ValueType::Many(ty) => {
             rsx! {
                 div {
                     div { class: "flex flex-col",
@@ -35,8 +90,9 @@ pub fn ModifyInput(node: Signal<NodeInput>) -> Element {
                             div { class: "whitespace-pre-line",
                                 ModifySingleValue {
                                     value,
+                                    ty,
                                     set_value: Rc::new(move |value| {
-                                        node.write_unchecked().value[0][i] = value;
+                                        edit_input(node, owner, |current| current[0][i] = value);
                                     })
                                 }
                             }
@@ -51,19 +107,37 @@ pub fn ModifyInput(node: Signal<NodeInput>) -> Element {
 #[derive(Clone, Props)]
 struct ModifySingleValueProps {
     value: PrimitiveValue,
+    /// The type the plugin declared for the input. This can be less specific than the value
+    ty: PrimitiveValueType,
     set_value: Rc<dyn Fn(PrimitiveValue)>,
 }
 
 impl PartialEq for ModifySingleValueProps {
     fn eq(&self, other: &Self) -> bool {
-        self.value == other.value
+        self.value == other.value && self.ty == other.ty
     }
 }
 
 fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
-    let ModifySingleValueProps { value, set_value } = props;
-    match value {
-        PrimitiveValue::Text(value) => {
+    let ModifySingleValueProps {
+        value,
+        ty,
+        set_value,
+    } = props;
+    match InputWidget::new(value, ty) {
+        InputWidget::TextField(value) => {
+            rsx! {
+                input {
+                    class: "border rounded focus:outline-none focus:border-blue-500",
+                    r#type: "text",
+                    value: "{value}",
+                    oninput: move |e| {
+                        set_value(PrimitiveValue::Text(e.value()));
+                    }
+                }
+            }
+        }
+        InputWidget::TextArea(value) => {
             rsx! {
                 textarea {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -74,7 +148,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 }
             }
         }
-        PrimitiveValue::File(file) => {
+        InputWidget::FilePicker(file) => {
             rsx! {
                 button {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -100,7 +174,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 "{file}"
             }
         }
-        PrimitiveValue::Folder(folder) => {
+        InputWidget::FolderPicker(folder) => {
             rsx! {
                 button {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -126,13 +200,8 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 "{folder}"
             }
         }
-        PrimitiveValue::Embedding(_)
-        | PrimitiveValue::Model(_)
-        | PrimitiveValue::EmbeddingModel(_)
-        | PrimitiveValue::Database(_)
-        | PrimitiveValue::Page(_)
-        | PrimitiveValue::Node(_) => show_primitive_value(&value),
-        PrimitiveValue::Number(value) => {
+        InputWidget::ReadOnly(value) => show_primitive_value(&value),
+        InputWidget::NumberField(value) => {
             rsx! {
                 input {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -144,7 +213,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 }
             }
         }
-        PrimitiveValue::Float(value) => {
+        InputWidget::FloatField(value) => {
             rsx! {
                 input {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -156,7 +225,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 }
             }
         }
-        PrimitiveValue::ModelType(ty) => {
+        InputWidget::ModelTypeDropdown(ty) => {
             rsx! {
                 select {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -181,7 +250,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 }
             }
         }
-        PrimitiveValue::EmbeddingModelType(ty) => {
+        InputWidget::EmbeddingModelTypeDropdown(ty) => {
             rsx! {
                 select {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -207,7 +276,7 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
                 }
             }
         }
-        PrimitiveValue::Boolean(val) => {
+        InputWidget::Checkbox(val) => {
             rsx! {
                 input {
                     class: "border rounded focus:outline-none focus:border-blue-500",
@@ -221,3 +290,41 @@ fn ModifySingleValue(props: ModifySingleValueProps) -> Element {
         }
     }
 }
+
+#[test]
+fn widgets_follow_the_declared_input_type() {
+    let text = || PrimitiveValue::Text("hello".to_string());
+    assert_eq!(
+        InputWidget::new(text(), PrimitiveValueType::Text),
+        InputWidget::TextArea("hello".to_string())
+    );
+    assert_eq!(
+        InputWidget::new(text(), PrimitiveValueType::Any),
+        InputWidget::TextField("hello".to_string())
+    );
+    assert_eq!(
+        InputWidget::new(
+            PrimitiveValue::ModelType(ModelType::MistralSeven),
+            PrimitiveValueType::ModelType
+        ),
+        InputWidget::ModelTypeDropdown(ModelType::MistralSeven)
+    );
+    assert_eq!(
+        InputWidget::new(
+            PrimitiveValue::File("notes.txt".to_string()),
+            PrimitiveValueType::File
+        ),
+        InputWidget::FilePicker("notes.txt".to_string())
+    );
+    assert_eq!(
+        InputWidget::new(PrimitiveValue::Number(3), PrimitiveValueType::Number),
+        InputWidget::NumberField(3)
+    );
+    assert!(matches!(
+        InputWidget::new(
+            PrimitiveValue::Embedding(Embedding { vector: vec![1.] }),
+            PrimitiveValueType::Embedding
+        ),
+        InputWidget::ReadOnly(_)
+    ));
+}
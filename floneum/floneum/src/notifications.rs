@@ -0,0 +1,95 @@
+use dioxus::prelude::*;
+
+use crate::settings::Complexity;
+use crate::use_application_state;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Notification {
+    pub id: usize,
+    pub message: String,
+}
+
+/// Messages shown in the corner of the canvas until they are dismissed
+#[derive(Default)]
+pub struct Notifications {
+    next_id: usize,
+    pub messages: Vec<Notification>,
+    /// The warning shown while the graph is large enough to slow down the canvas was dismissed
+    pub complexity_warning_dismissed: bool,
+}
+
+impl Notifications {
+    pub fn push(&mut self, message: impl Into<String>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.messages.push(Notification {
+            id,
+            message: message.into(),
+        });
+        id
+    }
+
+    pub fn dismiss(&mut self, id: usize) {
+        self.messages.retain(|notification| notification.id != id);
+    }
+}
+
+pub fn NotificationList() -> Element {
+    let mut application = use_application_state();
+    let current_application = application.read();
+    let messages = current_application.notifications.messages.clone();
+    let complexity = if current_application
+        .notifications
+        .complexity_warning_dismissed
+    {
+        Complexity::Normal
+    } else {
+        let graph = current_application.graph.inner.read();
+        graph.settings.complexity(graph.stats())
+    };
+    let complexity_warning = match complexity {
+        Complexity::Normal => None,
+        Complexity::Large => Some(
+            "This workflow is large and may feel slow. Collapsing nodes into macros or drawing straight connections can help.",
+        ),
+        Complexity::Overloaded => Some(
+            "This workflow is very large, so connections are drawn straight. Collapsing nodes into macros can help.",
+        ),
+    };
+
+    rsx! {
+        div {
+            position: "absolute",
+            bottom: "0",
+            right: "0",
+            class: "flex flex-col items-end gap-2 p-2 z-10",
+            if let Some(warning) = complexity_warning {
+                div {
+                    class: "flex flex-row items-center gap-2 bg-white border border-yellow-500 rounded-md shadow-lg px-3 py-2 text-sm",
+                    onmousedown: move |evt| evt.stop_propagation(),
+                    p { "{warning}" }
+                    button {
+                        class: "px-1 border rounded-md",
+                        title: "Dismiss",
+                        onclick: move |_| application.write().notifications.complexity_warning_dismissed = true,
+                        "x"
+                    }
+                }
+            }
+            for notification in messages {
+                div {
+                    key: "{notification.id}",
+                    class: "flex flex-row items-center gap-2 bg-white border rounded-md shadow-lg px-3 py-2 text-sm",
+                    onmousedown: move |evt| evt.stop_propagation(),
+                    p { "{notification.message}" }
+                    button {
+                        class: "px-1 border rounded-md",
+                        title: "Dismiss",
+                        onclick: move |_| application.write().notifications.dismiss(notification.id),
+                        "x"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,24 +1,44 @@
 use dioxus::prelude::*;
 
 use crate::{
-    graph::CurrentlyDragging, node::NODE_KNOB_SIZE, CurrentlyDraggingProps, DraggingIndex, Node,
-    VisualGraph,
+    accessibility::socket_label,
+    connect::{DropTarget, HoverValidity},
+    graph::CurrentlyDragging,
+    shortcuts::{handle_shortcut, ShortcutTarget},
+    use_application_state, CurrentlyDraggingProps, DraggingIndex, Node, VisualGraph,
 };
 
 #[component]
 pub fn Output(node: Signal<Node>, index: usize) -> Element {
+    let mut application = use_application_state();
     let current_node = node.read();
     let current_node_id = current_node.id;
     let color = current_node.output_color(index);
     let is_list = current_node.output_is_list(index);
+    let socket_size = application.read().graph.inner.read().settings.socket_size();
+    let label = {
+        let graph = application.read().graph;
+        let connected = graph
+            .inner
+            .read()
+            .output_has_connections(current_node_id, index);
+        let name = &current_node.outputs[index].read().definition.name;
+        socket_label("Output", name, current_node.output_type(index), connected)
+    };
 
     rsx! {
         button {
-            height: "{NODE_KNOB_SIZE}px",
-            width: "{NODE_KNOB_SIZE}px",
+            height: "{socket_size}px",
+            width: "{socket_size}px",
             border_radius: "50%",
             background_color: "{color}",
             display: "inline-block",
+            aria_label: "{label}",
+            onkeydown: move |evt| {
+                if handle_shortcut(application, ShortcutTarget::Socket(node, DraggingIndex::Output(index)), &evt) {
+                    evt.stop_propagation();
+                }
+            },
             onmounted: move |mount| async move {
                 let size = mount.get_client_rect().await.ok();
                 node.with_mut(|node| {
@@ -33,20 +53,41 @@ pub fn Output(node: Signal<Node>, index: usize) -> Element {
             onmousedown: move |evt| {
                 let mut graph: VisualGraph = consume_context();
                 let scaled_pos = graph.scale_screen_pos(evt.page_coordinates());
+                // Shift picks up every connection from the output so they can be moved to another
+                // output together
+                let has_connections = graph.inner.read().output_has_connections(current_node_id, index);
+                if evt.modifiers().contains(Modifiers::SHIFT) && has_connections {
+                    graph.start_rehoming(node, index, scaled_pos);
+                    evt.stop_propagation();
+                    return;
+                }
                 graph.inner.write().currently_dragging = Some(
                     CurrentlyDragging::Connection(CurrentlyDraggingProps {
                         from: node,
                         from_pos: scaled_pos,
                         index: DraggingIndex::Output(index),
                         to: Signal::new(scaled_pos),
+                        hover: Signal::new(HoverValidity::Empty),
                     }),
                 );
                 evt.stop_propagation();
             },
             onmouseup: move |evt| {
                 let mut graph: VisualGraph = consume_context();
-                graph.finish_connection(current_node_id, DraggingIndex::Output(index));
                 evt.stop_propagation();
+                if let Some(plan) = graph.finish_rehome(current_node_id, index) {
+                    if !plan.left_behind.is_empty() {
+                        let (_, reason) = plan.left_behind[0];
+                        application.write().notifications.push(format!(
+                            "Left {} of {} connections on the old output: {}",
+                            plan.left_behind.len(),
+                            plan.left_behind.len() + plan.moved.len(),
+                            reason
+                        ));
+                    }
+                    return;
+                }
+                graph.finish_connection(current_node_id, DropTarget::Socket(DraggingIndex::Output(index)));
             },
             onmousemove: move |evt| {
                 let mut graph: VisualGraph = consume_context();
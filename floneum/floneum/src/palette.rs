@@ -0,0 +1,407 @@
+use dioxus::prelude::*;
+use floneum_plugin::load_plugin_from_source;
+use floneum_plugin::plugins::main::types::{Definition, IoDefinition, ValueType};
+use floneumite::PackageIndexEntry;
+use petgraph::stable_graph::{DefaultIx, EdgeIndex, NodeIndex};
+
+use crate::accessibility::type_label;
+use crate::shape::NodeShape;
+use crate::shortcuts::keep_typing_out_of_shortcuts;
+use crate::theme::category_bg_color;
+use crate::{
+    insert_plugin_from_entry, use_application_state, use_package_manager, ApplicationState,
+    Colored, Point,
+};
+
+#[derive(Default)]
+pub struct PaletteState {
+    pub open: bool,
+    pub filter: Option<SocketFilter>,
+    /// The last filter the palette was opened with. The palette offers a toggle to turn it back on
+    /// until the palette is opened from the keyboard
+    pub last_filter: Option<SocketFilter>,
+    pub target: PaletteTarget,
+}
+
+impl PaletteState {
+    pub fn open(&mut self, target: PaletteTarget, filter: Option<SocketFilter>) {
+        self.open = true;
+        self.target = target;
+        self.filter = filter;
+        if filter.is_some() {
+            self.last_filter = filter;
+        }
+    }
+
+    /// Apply the last filter the palette was opened with, or remove it if it is already applied
+    pub fn toggle_last_filter(&mut self) {
+        self.filter = match self.filter {
+            Some(_) => None,
+            None => self.last_filter,
+        };
+    }
+
+    /// The filter plugins are sorted by. Even when the filter is turned off, plugins that fit the
+    /// last connection are listed first
+    pub fn sort_filter(&self) -> Option<SocketFilter> {
+        self.filter.or(self.last_filter)
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.target = PaletteTarget::Viewport;
+        self.filter = None;
+    }
+}
+
+/// Limits the palette to plugins that can be wired into a specific spot in the graph
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SocketFilter {
+    /// The plugin must have an input that accepts this type
+    pub input: Option<ValueType>,
+    /// The plugin must have an output that can be connected to this type
+    pub output: Option<ValueType>,
+}
+
+impl SocketFilter {
+    pub fn matches(&self, definition: &Definition) -> bool {
+        let accepts_input = self.input.map_or(true, |ty| {
+            definition
+                .inputs
+                .iter()
+                .any(|input| ty.compatible(&input.ty))
+        });
+        let produces_output = self.output.map_or(true, |ty| {
+            definition
+                .outputs
+                .iter()
+                .any(|output| output.ty.compatible(&ty))
+        });
+        accepts_input && produces_output
+    }
+}
+
+/// Where a plugin is listed in the palette. Plugins that fit the filter come first, then plugins
+/// that haven't been loaded yet
+fn palette_order(filter: Option<SocketFilter>, definition: Option<&Definition>) -> u8 {
+    match (filter, definition) {
+        (None, _) => 0,
+        (Some(filter), Some(definition)) if filter.matches(definition) => 0,
+        (Some(_), None) => 1,
+        (Some(_), Some(_)) => 2,
+    }
+}
+
+/// What happens to the node created from the palette
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum PaletteTarget {
+    /// Just add the node to the graph
+    #[default]
+    Viewport,
+    /// Insert the node in the middle of an existing connection
+    SplitEdge(EdgeIndex<DefaultIx>),
+    /// Place the node where a connection from an output was dropped and connect it to that output
+    ConnectFrom {
+        node: NodeIndex<DefaultIx>,
+        output: usize,
+        position: Point,
+    },
+}
+
+pub fn Palette() -> Element {
+    let mut application = use_application_state();
+    let plugins = use_package_manager();
+    let mut search_text = use_signal(String::new);
+    let current_application = application.read();
+    if !current_application.palette.open {
+        return None;
+    }
+    let filter = current_application.palette.filter;
+    let last_filter = current_application.palette.last_filter;
+    let sort_filter = current_application.palette.sort_filter();
+    let current_search_text = search_text();
+    let text_words: Vec<String> = current_search_text
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect();
+
+    rsx! {
+        div {
+            class: "absolute top-8 left-1/2 -translate-x-1/2 w-96 max-h-96 flex flex-col bg-white border rounded-md shadow-lg z-20",
+            onmousedown: move |evt| evt.stop_propagation(),
+            onmouseup: move |evt| evt.stop_propagation(),
+            onkeydown: move |evt| {
+                keep_typing_out_of_shortcuts(&evt);
+            },
+            input {
+                class: "border rounded-md p-2 m-2",
+                r#type: "text",
+                placeholder: "Search plugins",
+                autofocus: true,
+                oninput: move |event| {
+                    search_text.set(event.value());
+                }
+            }
+            if last_filter.is_some() {
+                label { class: "flex flex-row items-center gap-1 px-2 text-xs",
+                    input {
+                        r#type: "checkbox",
+                        checked: "{filter.is_some()}",
+                        onchange: move |_| application.write().palette.toggle_last_filter()
+                    }
+                    if filter == last_filter {
+                        "Filter by last type"
+                    } else {
+                        "Showing plugins that fit this connection"
+                    }
+                }
+            } else if filter.is_some() {
+                p { class: "px-2 text-xs opacity-50", "Showing plugins that fit this connection" }
+            }
+            div { class: "overflow-y-auto divide-y",
+                match &plugins {
+                    Some(plugins) => {
+                        let mut entries: Vec<_> = plugins
+                            .entries()
+                            .iter()
+                            .filter_map(|entry| {
+                                let meta = entry.meta()?;
+                                let name = meta.name.to_lowercase();
+                                let description = meta.description.to_lowercase();
+                                text_words
+                                    .iter()
+                                    .all(|word| name.contains(word) || description.contains(word))
+                                    .then(|| {
+                                        let definition = current_application.plugin_definition(&meta.name);
+                                        (palette_order(sort_filter, definition), entry)
+                                    })
+                            })
+                            .collect();
+                        entries.sort_by_key(|(order, _)| *order);
+                        rsx! {
+                        for (_, entry) in entries {
+                            // Changing the filter remounts the entries so they load the plugin definition if they need it
+                            PaletteEntry { key: "{entry.path().display()}-{filter.is_some()}", entry: entry.clone(), filter }
+                        }
+                        }
+                    }
+                    None => rsx! { "Loading..." },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PaletteEntry(entry: PackageIndexEntry, filter: Option<SocketFilter>) -> Element {
+    let mut application = use_application_state();
+    let plugin_name = entry
+        .meta()
+        .map(|meta| meta.name.clone())
+        .unwrap_or_default();
+    use_hook(|| {
+        // Plugins that have not been added to the graph yet are only loaded if the filter needs
+        // their sockets. They are kept with the other loaded plugins so they are only compiled once
+        if filter.is_some() && application.read().plugin_definition(&plugin_name).is_none() {
+            let entry = entry.clone();
+            let plugin_name = plugin_name.clone();
+            spawn(async move {
+                let plugin = {
+                    let read = application.read();
+                    load_plugin_from_source(entry, read.resource_storage.clone())
+                };
+                match plugin.definition().await {
+                    Ok(_) => {
+                        application
+                            .write()
+                            .plugins
+                            .entry(plugin_name)
+                            .or_insert(plugin);
+                    }
+                    Err(err) => log::error!("Failed to load plugin definition: {}", err),
+                }
+            });
+        }
+    });
+
+    let current_application = application.read();
+    let definition = current_application.plugin_definition(&plugin_name);
+    let compatible = match (&filter, definition) {
+        (Some(filter), Some(definition)) => Some(filter.matches(definition)),
+        (Some(_), None) => None,
+        (None, _) => Some(true),
+    };
+    if compatible == Some(false) {
+        return None;
+    }
+    let meta = entry.meta()?;
+    let name = meta.name.clone();
+    let description = meta.description.clone();
+    let color = category_bg_color(meta.category);
+    let loading_class = if compatible.is_none() {
+        "opacity-50"
+    } else {
+        ""
+    };
+
+    rsx! {
+        button {
+            class: "flex flex-row items-center gap-2 w-full text-left px-3 py-2 {loading_class}",
+            onclick: move |_| {
+                let entry = entry.clone();
+                async move {
+                    add_plugin_from_palette(application, entry).await;
+                }
+            },
+            div { class: "w-2 h-2 rounded-full flex-shrink-0 {color}" }
+            div { class: "min-w-0",
+                p { class: "text-sm font-semibold", "{name}" }
+                p { class: "truncate text-xs", "{description}" }
+                if let Some(definition) = definition {
+                    div { class: "flex flex-row flex-wrap items-center gap-1 mt-1",
+                        TypeChips { sockets: socket_types(&definition.inputs) }
+                        span { class: "text-xs opacity-50", "→" }
+                        TypeChips { sockets: socket_types(&definition.outputs) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn socket_types(sockets: &[IoDefinition]) -> Vec<(String, ValueType)> {
+    sockets
+        .iter()
+        .map(|socket| (socket.name.clone(), socket.ty))
+        .collect()
+}
+
+/// The names and types of a list of sockets
+#[component]
+fn TypeChips(sockets: Vec<(String, ValueType)>) -> Element {
+    if sockets.is_empty() {
+        return rsx! {
+            span { class: "text-xs opacity-50", "none" }
+        };
+    }
+
+    rsx! {
+        for (name, ty) in sockets {
+            span {
+                class: "px-1 border rounded-md text-xs",
+                border_color: "{ty.color()}",
+                title: "{name}",
+                "{type_label(ty)}"
+            }
+        }
+    }
+}
+
+async fn add_plugin_from_palette(
+    mut application: Signal<ApplicationState>,
+    entry: PackageIndexEntry,
+) {
+    let name = entry
+        .meta()
+        .map(|meta| meta.name.clone())
+        .unwrap_or_default();
+    let target = application.read().palette.target;
+    application.write().palette.close();
+    let id = match insert_plugin_from_entry(application, entry).await {
+        Ok(id) => id,
+        Err(err) => {
+            log::error!("Failed to insert plugin: {}", err);
+            return;
+        }
+    };
+    let mut graph = application.read().graph;
+    match target {
+        PaletteTarget::Viewport => {}
+        PaletteTarget::SplitEdge(edge) => {
+            if let Some(midpoint) = graph.edge_midpoint(edge) {
+                let mut node = graph.inner.read().graph[id];
+                node.write().position = midpoint;
+            }
+            if !graph.split_edge(edge, id) {
+                log::error!("{} doesn't fit in the connection", name);
+            }
+        }
+        PaletteTarget::ConnectFrom {
+            node: source,
+            output,
+            position,
+        } => {
+            let (output_ty, new_node) = {
+                let inner = graph.inner.read();
+                let Some(source_node) = inner.graph.node_weight(source) else {
+                    return;
+                };
+                let mut new_node = inner.graph[id];
+                new_node.write().position = position;
+                let new_node = NodeShape::new(&new_node.read());
+                (source_node.read().output_type(output), new_node)
+            };
+            let input = output_ty.and_then(|output_ty| {
+                new_node
+                    .inputs
+                    .iter()
+                    .position(|input| output_ty.compatible(&input.definition.ty))
+            });
+            match input {
+                Some(input) => {
+                    if let Err(err) = graph.connect(source, output, id, input) {
+                        log::error!("Failed to connect {}: {}", name, err);
+                    }
+                }
+                None => log::error!("{} has no input that fits the connection", name),
+            }
+        }
+    }
+}
+
+#[test]
+fn plugins_that_fit_the_last_connection_stay_first_with_the_filter_off() {
+    use crate::testing::{number, text};
+
+    let definition = |name: &str, inputs: &[ValueType]| Definition {
+        name: name.to_string(),
+        description: String::new(),
+        inputs: inputs
+            .iter()
+            .map(|&ty| IoDefinition {
+                name: "input".to_string(),
+                ty,
+            })
+            .collect(),
+        outputs: Vec::new(),
+        examples: Vec::new(),
+    };
+    let numbers = definition("numbers", &[number()]);
+    let texts = definition("texts", &[text()]);
+    let entries = [
+        ("numbers", Some(&numbers)),
+        ("unloaded", None),
+        ("texts", Some(&texts)),
+    ];
+
+    let mut palette = PaletteState::default();
+    let filter = SocketFilter {
+        input: Some(text()),
+        output: None,
+    };
+    palette.open(PaletteTarget::Viewport, Some(filter));
+    palette.toggle_last_filter();
+    assert_eq!(palette.filter, None);
+
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|(_, definition)| palette_order(palette.sort_filter(), *definition));
+    let names: Vec<_> = sorted.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["texts", "unloaded", "numbers"]);
+
+    // Opening the palette from the keyboard forgets the last connection, so nothing is reordered
+    palette.last_filter = None;
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|(_, definition)| palette_order(palette.sort_filter(), *definition));
+    let names: Vec<_> = sorted.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["numbers", "unloaded", "texts"]);
+}
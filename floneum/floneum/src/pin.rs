@@ -0,0 +1,145 @@
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+
+use crate::{Point, VisualGraphInner};
+
+/// The pan and zoom of the canvas
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Viewport {
+    pub pan_pos: Point,
+    pub zoom: f32,
+}
+
+impl Viewport {
+    pub fn graph_to_screen(&self, pos: Point) -> Point {
+        Point::new(
+            pos.x * self.zoom + self.pan_pos.x,
+            pos.y * self.zoom + self.pan_pos.y,
+        )
+    }
+
+    pub fn screen_to_graph(&self, pos: Point) -> Point {
+        Point::new(
+            (pos.x - self.pan_pos.x) / self.zoom,
+            (pos.y - self.pan_pos.y) / self.zoom,
+        )
+    }
+}
+
+/// The position in graph space that keeps a pinned node at the same place on screen after the
+/// viewport changes
+pub fn keep_on_screen(position: Point, old: Viewport, new: Viewport) -> Point {
+    new.screen_to_graph(old.graph_to_screen(position))
+}
+
+impl VisualGraphInner {
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            pan_pos: self.pan_pos,
+            zoom: self.zoom,
+        }
+    }
+
+    /// Where a node is drawn. Pinned nodes are drawn in screen space
+    pub fn drawn_position(&self, node: &crate::Node) -> Point {
+        if node.pinned {
+            self.graph_to_screen(node.position)
+        } else {
+            node.position
+        }
+    }
+
+    /// Pin a node to the screen or unpin it. The position is converted between graph space and
+    /// screen space so the node doesn't jump when it is toggled
+    pub fn toggle_pinned(&mut self, id: NodeIndex<DefaultIx>) {
+        let viewport = self.viewport();
+        let Some(mut node) = self.graph.node_weight(id).copied() else {
+            return;
+        };
+        let mut node = node.write();
+        node.position = if node.pinned {
+            viewport.graph_to_screen(node.position)
+        } else {
+            viewport.screen_to_graph(node.position)
+        };
+        node.pinned = !node.pinned;
+    }
+
+    /// Pan or zoom the canvas. Pinned nodes are moved in graph space so they stay still on screen
+    /// and their connections follow them
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        let old = self.viewport();
+        self.pan_pos = viewport.pan_pos;
+        self.zoom = viewport.zoom;
+        for id in self.graph.node_indices() {
+            let mut node = self.graph[id];
+            if node.read().pinned {
+                let position = node.read().position;
+                node.write().position = keep_on_screen(position, old, viewport);
+            }
+        }
+    }
+}
+
+#[test]
+fn pinned_nodes_stay_still_on_screen() {
+    let position = Point::new(50., 30.);
+    let old = Viewport {
+        pan_pos: Point::new(0., 0.),
+        zoom: 1.,
+    };
+    let on_screen = old.graph_to_screen(position);
+
+    for new in [
+        Viewport {
+            pan_pos: Point::new(100., -40.),
+            zoom: 1.,
+        },
+        Viewport {
+            pan_pos: Point::new(-25., 10.),
+            zoom: 2.,
+        },
+    ] {
+        let moved = keep_on_screen(position, old, new);
+        assert_eq!(new.graph_to_screen(moved), on_screen);
+        // An unpinned node keeps its graph position, so it moves on screen
+        assert_ne!(new.graph_to_screen(position), on_screen);
+    }
+}
+
+#[test]
+fn toggling_pin_keeps_the_node_still_on_screen() {
+    use crate::testing::{run_in_dioxus, test_plugin};
+    use crate::VisualGraph;
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let id = graph
+            .add_node(&test_plugin("string"), Point::new(50., 30.))
+            .await
+            .unwrap();
+        let mut inner = graph.inner;
+        let mut inner = inner.write();
+        inner.set_viewport(Viewport {
+            pan_pos: Point::new(-25., 10.),
+            zoom: 2.,
+        });
+        let node = inner.graph[id];
+        let drawn = |inner: &VisualGraphInner| inner.drawn_position(&node.read());
+        let before = drawn(&inner);
+
+        inner.toggle_pinned(id);
+        assert!(node.read().pinned);
+        assert_eq!(drawn(&inner), before);
+
+        // The pinned node stays put while the viewport moves
+        inner.set_viewport(Viewport {
+            pan_pos: Point::new(100., -40.),
+            zoom: 1.5,
+        });
+        assert_eq!(drawn(&inner), before);
+
+        inner.toggle_pinned(id);
+        assert!(!node.read().pinned);
+        assert_eq!(drawn(&inner), before);
+    });
+}
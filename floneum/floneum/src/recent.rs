@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use dioxus::{html::geometry::PagePoint, prelude::*};
+
+use crate::{use_application_state, Point, VisualGraph};
+
+/// How many plugins the recently added list remembers
+const RECENTLY_ADDED_LIMIT: usize = 5;
+
+/// The plugins that nodes were created from most recently, newest first
+#[derive(Default)]
+pub struct RecentlyAdded {
+    names: VecDeque<String>,
+}
+
+impl RecentlyAdded {
+    /// Remember that a node was created from a plugin. Adding a plugin that is already in the
+    /// list moves it to the front
+    pub fn push(&mut self, name: &str) {
+        self.names.retain(|other| other != name);
+        self.names.push_front(name.to_string());
+        self.names.truncate(RECENTLY_ADDED_LIMIT);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+}
+
+/// Buttons that add another node from a recently added plugin in the middle of the viewport
+pub fn RecentlyAddedBar() -> Element {
+    let mut application = use_application_state();
+    let names: Vec<String> = application
+        .read()
+        .recently_added
+        .names()
+        .map(str::to_string)
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+
+    rsx! {
+        div {
+            class: "absolute top-0 left-1/2 -translate-x-1/2 flex flex-row items-center gap-1 p-1 text-xs z-10",
+            onmousedown: move |evt| evt.stop_propagation(),
+            span { class: "opacity-50", "Recent" }
+            for name in names {
+                button {
+                    key: "{name}",
+                    class: "px-2 py-1 border rounded-md bg-white",
+                    title: "Add another {name} node",
+                    onclick: {
+                        let name = name.clone();
+                        move |_| {
+                            let name = name.clone();
+                            async move {
+                                // Clone the plugin so the application state isn't borrowed while
+                                // the instance is created
+                                let plugin = application.read().get_plugin(&name).cloned();
+                                let Some(plugin) = plugin else {
+                                    log::error!("Plugin {} is not loaded", name);
+                                    return;
+                                };
+                                let graph = application.read().graph;
+                                match graph.add_node(&plugin, graph.viewport_center()).await {
+                                    Ok(_) => application.write().recently_added.push(&name),
+                                    Err(err) => log::error!("Failed to insert plugin: {}", err),
+                                }
+                            }
+                        }
+                    },
+                    "{name}"
+                }
+            }
+        }
+    }
+}
+
+impl VisualGraph {
+    /// The point in graph space in the middle of the canvas
+    pub fn viewport_center(&self) -> Point {
+        let size = self
+            .inner
+            .read()
+            .rendered_size
+            .map(|rect| rect.size)
+            .unwrap_or_default();
+        self.scale_screen_pos(PagePoint::new(size.width / 2., size.height / 2.))
+    }
+}
+
+#[test]
+fn recently_added_is_capped_and_newest_first() {
+    let mut recent = RecentlyAdded::default();
+    for name in ["A", "B", "C", "D", "E", "F"] {
+        recent.push(name);
+    }
+    assert_eq!(
+        recent.names().collect::<Vec<_>>(),
+        ["F", "E", "D", "C", "B"]
+    );
+
+    // Adding a plugin again moves it to the front instead of listing it twice
+    recent.push("C");
+    assert_eq!(
+        recent.names().collect::<Vec<_>>(),
+        ["C", "F", "E", "D", "B"]
+    );
+}
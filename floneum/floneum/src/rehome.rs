@@ -0,0 +1,160 @@
+use dioxus::prelude::*;
+use petgraph::{
+    graph::NodeIndex,
+    stable_graph::{DefaultIx, EdgeIndex},
+    visit::EdgeRef,
+};
+
+use crate::connect::{check_connection, CheckedConnection, ConnectError};
+use crate::graph::CurrentlyDragging;
+use crate::shape::GraphShape;
+use crate::{DraggingIndex, Node, Point, VisualGraph, VisualGraphInner};
+
+/// Every connection from one output being dragged to another output at once
+#[derive(PartialEq, Clone, Copy)]
+pub struct RehomeDragInfo {
+    pub from: Signal<Node>,
+    pub output: usize,
+    pub to: Signal<Point>,
+}
+
+/// The changes moving the connections of an output to another output makes
+#[derive(Default, Debug)]
+pub struct RehomePlan {
+    /// Connections that are replaced by a connection from the new output
+    pub moved: Vec<(EdgeIndex<DefaultIx>, CheckedConnection)>,
+    /// Connections that the new output can't feed. They stay on the old output
+    pub left_behind: Vec<(EdgeIndex<DefaultIx>, ConnectError)>,
+}
+
+/// Check every connection from an output against a new output with the same rules as dragging a
+/// single connection
+pub fn plan_rehome(
+    graph: &GraphShape,
+    from: (NodeIndex<DefaultIx>, usize),
+    to: (NodeIndex<DefaultIx>, usize),
+) -> RehomePlan {
+    let mut plan = RehomePlan::default();
+    if from == to {
+        return plan;
+    }
+    for edge in graph
+        .edges_directed(from.0, petgraph::Direction::Outgoing)
+        .filter(|edge| edge.weight().start == from.1)
+    {
+        match check_connection(
+            graph,
+            (to.0, DraggingIndex::Output(to.1)),
+            (edge.target(), DraggingIndex::Input(edge.weight().end)),
+        ) {
+            Ok(connection) => plan.moved.push((edge.id(), connection)),
+            Err(err) => plan.left_behind.push((edge.id(), err)),
+        }
+    }
+    plan
+}
+
+impl VisualGraphInner {
+    pub fn output_has_connections(&self, node: NodeIndex<DefaultIx>, output: usize) -> bool {
+        self.graph
+            .edges_directed(node, petgraph::Direction::Outgoing)
+            .any(|edge| edge.weight().read().start == output)
+    }
+
+    pub fn apply_rehome(&mut self, plan: &RehomePlan) {
+        // The new connection goes to the same input as the old one, so inserting it replaces the
+        // old connection
+        for (_, connection) in &plan.moved {
+            self.insert_edge(
+                connection.source,
+                connection.target,
+                Signal::new(connection.edge.clone()),
+            );
+            // The input is fed by a different output now, so the outputs are out of date
+            let mut target = self.graph[connection.target];
+            target.write().dirty = true;
+        }
+    }
+}
+
+impl VisualGraph {
+    /// Pick up every connection from an output
+    pub fn start_rehoming(&mut self, from: Signal<Node>, output: usize, position: Point) {
+        self.inner.write().currently_dragging = Some(CurrentlyDragging::Rehome(RehomeDragInfo {
+            from,
+            output,
+            to: Signal::new(position),
+        }));
+    }
+
+    /// Drop the connections that are being dragged on an output. Returns the changes that were
+    /// made if connections were being dragged
+    pub fn finish_rehome(
+        &mut self,
+        node_id: NodeIndex<DefaultIx>,
+        output: usize,
+    ) -> Option<RehomePlan> {
+        let mut inner = self.inner.write();
+        let Some(CurrentlyDragging::Rehome(drag)) = inner.currently_dragging else {
+            return None;
+        };
+        inner.currently_dragging = None;
+        let from = drag.from.read().id;
+        let plan = plan_rehome(&inner.shape(), (from, drag.output), (node_id, output));
+        inner.apply_rehome(&plan);
+        inner.edge_scroll = None;
+        Some(plan)
+    }
+}
+
+#[test]
+fn connections_that_fit_the_new_output_are_moved() {
+    use crate::shape::NodeShape;
+    use crate::testing::{input, text};
+    use crate::Edge;
+
+    let mut graph = GraphShape::default();
+    let old = graph.add_node(NodeShape::test(&[], &[text()]));
+    let new = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let first = graph.add_node(NodeShape::test(&[text()], &[]));
+    let second = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let to_first = graph.add_edge(old, first, Edge::new(0, input(0)));
+    let to_second = graph.add_edge(old, second, Edge::new(0, input(0)));
+    // The second node feeds the new output, so moving its connection would create a cycle
+    graph.add_edge(second, new, Edge::new(0, input(0)));
+
+    let plan = plan_rehome(&graph, (old, 0), (new, 0));
+    assert_eq!(plan.moved.len(), 1);
+    let (moved, connection) = &plan.moved[0];
+    assert_eq!(*moved, to_first);
+    assert_eq!((connection.source, connection.target), (new, first));
+    assert_eq!(plan.left_behind, [(to_second, ConnectError::Cycle)]);
+
+    // Dropping the connections back on the same output changes nothing
+    let plan = plan_rehome(&graph, (old, 0), (old, 0));
+    assert!(plan.moved.is_empty() && plan.left_behind.is_empty());
+}
+
+#[test]
+fn moved_connections_mark_their_targets_dirty() {
+    use crate::testing::{input, run_in_dioxus, test_plugin};
+
+    run_in_dioxus(|| async {
+        let graph = VisualGraph::default();
+        let number = test_plugin("number");
+        let old = graph.add_node(&number, Point::new(0., 0.)).await.unwrap();
+        let new = graph.add_node(&number, Point::new(0., 200.)).await.unwrap();
+        let target = graph.add_node(&number, Point::new(200., 0.)).await.unwrap();
+        let mut inner = graph.inner;
+        let mut inner = inner.write();
+        inner.insert_edge(old, target, Signal::new(crate::Edge::new(0, input(0))));
+
+        let plan = plan_rehome(&inner.shape(), (old, 0), (new, 0));
+        inner.apply_rehome(&plan);
+        assert!(inner.edge_exists(new, 0, target, input(0)));
+        assert!(!inner.edge_exists(old, 0, target, input(0)));
+        assert_eq!(inner.graph.edge_count(), 1);
+        assert!(inner.graph[target].read().dirty);
+        assert!(!inner.graph[new].read().dirty);
+    });
+}
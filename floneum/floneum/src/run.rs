@@ -0,0 +1,201 @@
+use std::future::Future;
+
+use anyhow::anyhow;
+use floneum_plugin::plugins::main::types::PrimitiveValue;
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+
+use crate::VisualGraph;
+
+/// How many nodes of a run of the whole graph have finished
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RunProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// How a run of the graph ended
+#[derive(Clone, PartialEq, Debug)]
+pub enum RunOutcome {
+    Finished,
+    /// The run was cancelled before these nodes started
+    Cancelled {
+        not_started: Vec<NodeIndex<DefaultIx>>,
+    },
+}
+
+/// Run nodes one at a time in order, reporting progress after each node finishes. Cancellation is
+/// checked before each node starts. The run stops at the first node that fails
+pub async fn run_in_order<F, Fut>(
+    order: &[NodeIndex<DefaultIx>],
+    mut run: F,
+    mut report: impl FnMut(RunProgress),
+    cancelled: impl Fn() -> bool,
+) -> anyhow::Result<RunOutcome>
+where
+    F: FnMut(NodeIndex<DefaultIx>) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let total = order.len();
+    for (index, &id) in order.iter().enumerate() {
+        if cancelled() {
+            return Ok(RunOutcome::Cancelled {
+                not_started: order[index..].to_vec(),
+            });
+        }
+        run(id).await?;
+        report(RunProgress {
+            completed: index + 1,
+            total,
+        });
+    }
+    Ok(RunOutcome::Finished)
+}
+
+impl VisualGraph {
+    /// Run every node in the graph after the nodes it depends on
+    pub async fn run_all(&self) {
+        let mut inner = self.inner;
+        if inner.read().run_progress.is_some() {
+            return;
+        }
+        let order = match petgraph::algo::toposort(&inner.read().graph, None) {
+            Ok(order) => order,
+            Err(cycle) => {
+                log::error!("Cannot run a graph with a cycle at {:?}", cycle.node_id());
+                return;
+            }
+        };
+        inner.write().run_progress = Some(RunProgress {
+            completed: 0,
+            total: order.len(),
+        });
+        let graph = *self;
+        let result = run_in_order(
+            &order,
+            move |id| async move { graph.execute_node(id).await },
+            move |progress| inner.write().run_progress = Some(progress),
+            move || inner.read().cancel_requested,
+        )
+        .await;
+        match result {
+            Ok(RunOutcome::Finished) => {}
+            Ok(RunOutcome::Cancelled { not_started }) => {
+                let current_graph = inner.read();
+                for id in not_started {
+                    if let Some(mut node) = current_graph.graph.node_weight(id).copied() {
+                        let mut node = node.write();
+                        node.running = false;
+                        node.queued = false;
+                    }
+                }
+            }
+            Err(err) => log::error!("Stopped running the graph: {}", err),
+        }
+        let mut inner = inner.write();
+        inner.run_progress = None;
+        inner.cancel_requested = false;
+    }
+
+    /// Stop the current run before the next node starts. Nodes that are already running finish
+    pub fn cancel_run(&self) {
+        let mut inner = self.inner;
+        let mut inner = inner.write();
+        if inner.run_progress.is_some() {
+            inner.cancel_requested = true;
+        }
+    }
+
+    /// Run a single node and wait for its outputs. Running one node and running the whole graph both
+    /// go through this. The nodes connected to its outputs are not queued. Frozen nodes are not run
+    pub async fn execute_node(&self, id: NodeIndex<DefaultIx>) -> anyhow::Result<()> {
+        let mut node = self.inner.read().graph[id];
+        // Frozen nodes pass the outputs they were frozen with on instead of running
+        let frozen = node.read().frozen_outputs();
+        let outputs = match frozen {
+            Some(outputs) => outputs,
+            None => self.invoke_node(id).await?,
+        };
+        node.write().set_outputs(&outputs);
+        Ok(())
+    }
+
+    /// Run the plugin of a node with the current values of its inputs
+    async fn invoke_node(
+        &self,
+        id: NodeIndex<DefaultIx>,
+    ) -> anyhow::Result<Vec<Vec<PrimitiveValue>>> {
+        if !self.set_input_nodes(id) {
+            return Err(anyhow!("Node {:?} is waiting on other nodes", id));
+        }
+        let mut node = self.inner.read().graph[id];
+        let fut = {
+            let mut current_node = node.write();
+            current_node.running = true;
+            let inputs = current_node
+                .inputs
+                .iter()
+                .map(|input| input.read().value())
+                .collect();
+            current_node.instance.run(inputs)
+        };
+        // Don't hold the write over an await point
+        let result = fut.await;
+        let mut current_node = node.write();
+        current_node.running = false;
+        match result.as_deref() {
+            Some(Ok(result)) => Ok(result.clone()),
+            Some(Err(err)) => {
+                current_node.error = Some(err.to_string());
+                Err(anyhow!("Node {:?} failed: {}", id, err))
+            }
+            None => Err(anyhow!("Node {:?} stopped without a result", id)),
+        }
+    }
+}
+
+#[tokio::test]
+async fn three_node_run_reports_each_node() {
+    let order = [NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)];
+    let mut reported = Vec::new();
+    let outcome = run_in_order(
+        &order,
+        |_| async { Ok(()) },
+        |progress| reported.push(progress),
+        || false,
+    )
+    .await
+    .unwrap();
+    assert_eq!(outcome, RunOutcome::Finished);
+    let completed: Vec<_> = reported.iter().map(|progress| progress.completed).collect();
+    assert_eq!(completed, [1, 2, 3]);
+    assert!(reported.iter().all(|progress| progress.total == 3));
+}
+
+#[tokio::test]
+async fn cancelling_stops_before_the_next_node() {
+    use std::cell::{Cell, RefCell};
+
+    let order = [NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)];
+    let cancelled = Cell::new(false);
+    let ran = RefCell::new(Vec::new());
+    let outcome = run_in_order(
+        &order,
+        |id| {
+            ran.borrow_mut().push(id);
+            // The first node is cancelled while it is running
+            cancelled.set(true);
+            async { Ok(()) }
+        },
+        |_| {},
+        || cancelled.get(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(*ran.borrow(), [order[0]]);
+    assert_eq!(
+        outcome,
+        RunOutcome::Cancelled {
+            not_started: vec![order[1], order[2]]
+        }
+    );
+}
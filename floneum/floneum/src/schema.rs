@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use floneum_plugin::plugins::main::types::{Definition, IoDefinition};
+use serde_json::{json, Value};
+
+use crate::accessibility::type_label;
+use crate::VisualGraph;
+
+fn sockets_schema(sockets: &[IoDefinition]) -> Value {
+    sockets
+        .iter()
+        .map(|socket| {
+            json!({
+                "name": socket.name,
+                "type": type_label(socket.ty),
+            })
+        })
+        .collect()
+}
+
+/// The interface of every distinct plugin, sorted by name. Plugins that are used by several nodes
+/// are only listed once
+pub fn plugin_schema<'a>(definitions: impl IntoIterator<Item = &'a Definition>) -> Value {
+    let plugins: BTreeMap<_, _> = definitions
+        .into_iter()
+        .map(|definition| (definition.name.as_str(), definition))
+        .collect();
+    plugins
+        .into_values()
+        .map(|definition| {
+            json!({
+                "name": definition.name,
+                "description": definition.description,
+                "inputs": sockets_schema(&definition.inputs),
+                "outputs": sockets_schema(&definition.outputs),
+            })
+        })
+        .collect()
+}
+
+impl VisualGraph {
+    /// Describe the inputs and outputs of the plugins used in the graph, so other tools can
+    /// understand a flow without loading the whole graph
+    pub fn export_plugin_schema(&self) -> Value {
+        let inner = self.inner.read();
+        let nodes: Vec<_> = inner
+            .graph
+            .node_weights()
+            .map(|node| node.read().instance.metadata().clone())
+            .collect();
+        plugin_schema(&nodes)
+    }
+}
+
+#[test]
+fn schema_lists_each_plugin_once() {
+    use floneum_plugin::plugins::main::types::{PrimitiveValueType, ValueType};
+
+    let socket = |name: &str, ty| IoDefinition {
+        name: name.to_string(),
+        ty,
+    };
+    let definition = |name: &str, inputs, outputs| Definition {
+        name: name.to_string(),
+        description: format!("{name} description"),
+        inputs,
+        outputs,
+        examples: Vec::new(),
+    };
+    let text = ValueType::Single(PrimitiveValueType::Text);
+    let numbers = ValueType::Many(PrimitiveValueType::Number);
+    let generate = definition(
+        "Generate Text",
+        vec![socket("prompt", text)],
+        vec![socket("output", text)],
+    );
+    let split = definition(
+        "Split",
+        vec![socket("text", text)],
+        vec![socket("lengths", numbers)],
+    );
+
+    let schema = plugin_schema([&split, &generate, &split]);
+    assert_eq!(
+        schema,
+        json!([
+            {
+                "name": "Generate Text",
+                "description": "Generate Text description",
+                "inputs": [{ "name": "prompt", "type": "text" }],
+                "outputs": [{ "name": "output", "type": "text" }],
+            },
+            {
+                "name": "Split",
+                "description": "Split description",
+                "inputs": [{ "name": "text", "type": "text" }],
+                "outputs": [{ "name": "lengths", "type": "list of number" }],
+            },
+        ])
+    );
+}
@@ -0,0 +1,132 @@
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::PrimitiveValue;
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+
+use crate::node_value::Named;
+use crate::shortcuts::keep_typing_out_of_shortcuts;
+use crate::{use_application_state, VisualGraphInner};
+
+/// Finds nodes that are already in the graph. The palette searches plugins that can be added
+#[derive(Default)]
+pub struct CanvasSearchState {
+    pub query: String,
+    pub matches: Vec<NodeIndex<DefaultIx>>,
+    /// The index into `matches` of the node that was focused last
+    pub current: Option<usize>,
+}
+
+impl CanvasSearchState {
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty()
+    }
+
+    /// Move to the next match. Returns the node that should be focused
+    pub fn next_match(&mut self) -> Option<NodeIndex<DefaultIx>> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let next = self
+            .current
+            .map_or(0, |current| (current + 1) % self.matches.len());
+        self.current = Some(next);
+        Some(self.matches[next])
+    }
+}
+
+/// The text of a value that can be searched for
+fn searchable_text(value: &PrimitiveValue) -> Option<String> {
+    match value {
+        PrimitiveValue::Text(text) | PrimitiveValue::File(text) | PrimitiveValue::Folder(text) => {
+            Some(text.clone())
+        }
+        PrimitiveValue::Number(number) => Some(number.to_string()),
+        PrimitiveValue::Float(number) => Some(number.to_string()),
+        PrimitiveValue::Boolean(boolean) => Some(boolean.to_string()),
+        PrimitiveValue::ModelType(ty) => Some(ty.name().to_string()),
+        PrimitiveValue::EmbeddingModelType(ty) => Some(ty.name().to_string()),
+        PrimitiveValue::Embedding(_)
+        | PrimitiveValue::Model(_)
+        | PrimitiveValue::EmbeddingModel(_)
+        | PrimitiveValue::Database(_)
+        | PrimitiveValue::Page(_)
+        | PrimitiveValue::Node(_) => None,
+    }
+}
+
+impl VisualGraphInner {
+    /// Find the nodes whose plugin name or input values contain the query
+    pub fn search(&self, query: &str) -> Vec<NodeIndex<DefaultIx>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.nodes_in_render_order()
+            .into_iter()
+            .filter(|&id| {
+                let node = self.graph[id].read();
+                let name_matches = node
+                    .instance
+                    .metadata()
+                    .name
+                    .to_lowercase()
+                    .contains(&query);
+                name_matches
+                    || node.inputs.iter().any(|input| {
+                        input.read().value().iter().any(|value| {
+                            searchable_text(value)
+                                .is_some_and(|text| text.to_lowercase().contains(&query))
+                        })
+                    })
+            })
+            .collect()
+    }
+}
+
+pub fn CanvasSearch() -> Element {
+    let mut application = use_application_state();
+    let current_application = application.read();
+    let search = &current_application.canvas_search;
+    let query = search.query.clone();
+    let status = match (search.is_active(), search.matches.len(), search.current) {
+        (false, _, _) => String::new(),
+        (true, 0, _) => "No matches".to_string(),
+        (true, count, Some(current)) => format!("{}/{}", current + 1, count),
+        (true, count, None) => format!("{count} matches"),
+    };
+
+    rsx! {
+        div {
+            position: "absolute",
+            top: "0",
+            right: "0",
+            class: "flex flex-row items-center gap-2 border-b-2 border-l-2 rounded-bl-md p-2 z-10",
+            onmousedown: move |evt| evt.stop_propagation(),
+            onkeydown: move |evt| {
+                keep_typing_out_of_shortcuts(&evt);
+                if evt.key() == Key::Enter {
+                    let mut application = application.write();
+                    if let Some(id) = application.canvas_search.next_match() {
+                        application.focus_node(id);
+                    }
+                }
+            },
+            input {
+                class: "border rounded-md px-2 py-1 text-sm",
+                r#type: "search",
+                placeholder: "Find in workflow",
+                value: "{query}",
+                oninput: move |event| {
+                    let query = event.value();
+                    let mut application = application.write();
+                    let matches = application.graph.inner.read().search(&query);
+                    application.canvas_search = CanvasSearchState {
+                        query,
+                        matches,
+                        current: None,
+                    };
+                }
+            }
+            span { class: "text-xs opacity-50", "{status}" }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+
+use crate::VisualGraphInner;
+
+/// How connections between nodes are drawn
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RoutingMode {
+    #[default]
+    Curved,
+    /// Straight lines are cheaper to draw in very large graphs
+    Straight,
+}
+
+/// Options for how the canvas behaves
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphSettings {
+    pub routing: RoutingMode,
+    /// How close the cursor needs to be to a socket for a dragged connection to snap to it
+    pub snap_distance: f32,
+    /// The radius sockets are drawn with
+    pub socket_radius: f64,
+    /// Draw a hop where connections cross. Finding the crossings is expensive, so graphs over the
+    /// soft limit never draw hops
+    pub crossing_hops: bool,
+    /// Above this many nodes or connections the user is warned that the canvas may be slow
+    pub soft_node_limit: usize,
+    pub soft_edge_limit: usize,
+    /// Above this many nodes or connections connections are always drawn straight
+    pub hard_node_limit: usize,
+    pub hard_edge_limit: usize,
+}
+
+impl Default for GraphSettings {
+    fn default() -> Self {
+        Self {
+            routing: RoutingMode::default(),
+            snap_distance: 15.,
+            socket_radius: 5.,
+            crossing_hops: false,
+            soft_node_limit: 100,
+            soft_edge_limit: 200,
+            hard_node_limit: 300,
+            hard_edge_limit: 600,
+        }
+    }
+}
+
+/// The size of a graph
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct GraphStats {
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// How close a graph is to being too large to render smoothly
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Complexity {
+    Normal,
+    /// Over the soft limit
+    Large,
+    /// Over the hard limit
+    Overloaded,
+}
+
+impl GraphSettings {
+    pub fn socket_size(&self) -> f64 {
+        self.socket_radius * 2.
+    }
+
+    pub fn complexity(&self, stats: GraphStats) -> Complexity {
+        if stats.nodes > self.hard_node_limit || stats.edges > self.hard_edge_limit {
+            Complexity::Overloaded
+        } else if stats.nodes > self.soft_node_limit || stats.edges > self.soft_edge_limit {
+            Complexity::Large
+        } else {
+            Complexity::Normal
+        }
+    }
+
+    pub fn draw_crossing_hops(&self, stats: GraphStats) -> bool {
+        self.crossing_hops && self.complexity(stats) == Complexity::Normal
+    }
+
+    /// The routing mode connections are drawn with. Graphs over the hard limit always use
+    /// straight connections
+    pub fn effective_routing(&self, stats: GraphStats) -> RoutingMode {
+        match self.complexity(stats) {
+            Complexity::Overloaded => RoutingMode::Straight,
+            _ => self.routing,
+        }
+    }
+}
+
+impl VisualGraphInner {
+    /// Count the nodes and connections that are drawn on the canvas. Nodes and connections inside
+    /// a macro are not drawn
+    pub fn stats(&self) -> GraphStats {
+        let nodes = self
+            .graph
+            .node_indices()
+            .filter(|&id| self.macro_containing(id).is_none())
+            .count()
+            + self.macros.len();
+        let edges = self
+            .graph
+            .edge_references()
+            .filter(|edge| !self.edge_is_inside_macro(edge.source(), edge.target()))
+            .count();
+        GraphStats { nodes, edges }
+    }
+}
+
+#[test]
+fn crossing_the_hard_limit_draws_straight_connections() {
+    let settings = GraphSettings {
+        soft_edge_limit: 2,
+        hard_edge_limit: 4,
+        ..Default::default()
+    };
+    let stats = |edges| GraphStats { nodes: 1, edges };
+
+    assert_eq!(settings.complexity(stats(2)), Complexity::Normal);
+    assert_eq!(settings.effective_routing(stats(2)), RoutingMode::Curved);
+    assert_eq!(settings.complexity(stats(3)), Complexity::Large);
+    assert_eq!(settings.effective_routing(stats(4)), RoutingMode::Curved);
+    assert_eq!(settings.complexity(stats(5)), Complexity::Overloaded);
+    assert_eq!(settings.effective_routing(stats(5)), RoutingMode::Straight);
+
+    // Choosing straight connections works below the limit too
+    let settings = GraphSettings {
+        routing: RoutingMode::Straight,
+        ..settings
+    };
+    assert_eq!(settings.effective_routing(stats(0)), RoutingMode::Straight);
+
+    // Crossing hops are skipped as soon as the graph is over the soft limit
+    let settings = GraphSettings {
+        crossing_hops: true,
+        ..settings
+    };
+    assert!(settings.draw_crossing_hops(stats(2)));
+    assert!(!settings.draw_crossing_hops(stats(3)));
+}
@@ -0,0 +1,96 @@
+use dioxus::html::geometry::euclid::Rect;
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::{IoDefinition, ValueType};
+use petgraph::stable_graph::StableGraph;
+
+use crate::edge::{Connection, ConnectionType};
+use crate::{Edge, Node, Point, VisualGraphInner};
+
+/// A plain snapshot of the graph that doesn't hold any signals or plugin instances.
+///
+/// Node and edge indices are the same as the indices in the live graph.
+pub type GraphShape = StableGraph<NodeShape, Edge>;
+
+#[derive(Clone, Debug)]
+pub struct NodeShape {
+    pub name: String,
+    pub position: Point,
+    pub rendered_size: Option<Rect<f64, f64>>,
+    pub inputs: Vec<InputShape>,
+    pub outputs: Vec<IoDefinition>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InputShape {
+    pub definition: IoDefinition,
+    /// If the input has any value set without a connection
+    pub has_value: bool,
+}
+
+impl NodeShape {
+    pub fn new(node: &Node) -> Self {
+        Self {
+            name: node.instance.metadata().name.clone(),
+            position: node.position,
+            rendered_size: node.rendered_size,
+            inputs: node
+                .inputs
+                .iter()
+                .map(|input| {
+                    let input = input.read();
+                    InputShape {
+                        definition: input.definition.clone(),
+                        has_value: !input.value().is_empty(),
+                    }
+                })
+                .collect(),
+            outputs: node
+                .outputs
+                .iter()
+                .map(|output| output.read().definition.clone())
+                .collect(),
+        }
+    }
+
+    pub fn input_type(&self, index: Connection) -> Option<ValueType> {
+        let ty = self.inputs.get(index.index)?.definition.ty;
+        match index.ty {
+            ConnectionType::Single => Some(ty),
+            ConnectionType::Element(_) => match ty {
+                ValueType::Many(ty) => Some(ValueType::Many(ty)),
+                ValueType::Single(_) => None,
+            },
+        }
+    }
+
+    pub fn output_type(&self, index: usize) -> Option<ValueType> {
+        self.outputs.get(index).map(|output| output.ty)
+    }
+}
+
+/// Find an input on the node that accepts `output_ty` and an output on the node that can be
+/// connected to `input_ty`. Returns the (input, output) indices
+pub fn split_sockets(
+    node: &NodeShape,
+    output_ty: ValueType,
+    input_ty: ValueType,
+) -> Option<(usize, usize)> {
+    let input = node
+        .inputs
+        .iter()
+        .position(|input| output_ty.compatible(&input.definition.ty))?;
+    let output = node
+        .outputs
+        .iter()
+        .position(|output| output.ty.compatible(&input_ty))?;
+    Some((input, output))
+}
+
+impl VisualGraphInner {
+    pub fn shape(&self) -> GraphShape {
+        self.graph.map(
+            |_, node| NodeShape::new(&node.read()),
+            |_, edge| edge.read().clone(),
+        )
+    }
+}
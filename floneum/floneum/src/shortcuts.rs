@@ -0,0 +1,316 @@
+use dioxus::prelude::*;
+
+use crate::accessibility::nudge;
+use crate::duplicate::duplicate_selection;
+use crate::palette::PaletteTarget;
+use crate::{use_application_state, ApplicationState, DraggingIndex, Node};
+
+/// Every keyboard shortcut in the canvas. The help overlay is generated from this list
+pub const SHORTCUTS: &[Shortcut] = &[
+    Shortcut {
+        keys: Keys::leader("k"),
+        description: "Open the plugin palette",
+        handler: ShortcutHandler::Canvas(|mut application| {
+            // Opening the palette from the keyboard starts from a clean slate
+            let mut application = application.write();
+            application.palette.last_filter = None;
+            application.palette.open(PaletteTarget::Viewport, None);
+        }),
+    },
+    Shortcut {
+        keys: Keys::leader("d"),
+        description: "Duplicate the selected nodes",
+        handler: ShortcutHandler::Canvas(|application| {
+            spawn(async move {
+                if let Err(err) = duplicate_selection(application).await {
+                    log::error!("Failed to duplicate the selection: {}", err);
+                }
+            });
+        }),
+    },
+    Shortcut {
+        keys: Keys::leader("z"),
+        description: "Undo the last duplicate",
+        handler: ShortcutHandler::Canvas(|mut application| {
+            application.write().undo();
+        }),
+    },
+    Shortcut {
+        keys: Keys::leader("g"),
+        description: "Collapse the selected nodes into a macro",
+        handler: ShortcutHandler::Canvas(|mut application| {
+            application.write().collapse_selection();
+        }),
+    },
+    Shortcut {
+        keys: Keys::character("?"),
+        description: "Show or hide this list of shortcuts",
+        handler: ShortcutHandler::Canvas(|mut application| {
+            let mut application = application.write();
+            application.shortcut_help_open = !application.shortcut_help_open;
+        }),
+    },
+    Shortcut {
+        keys: Keys::escape(),
+        description: "Close the palette or this list of shortcuts, clear the search and drop any connection that is being moved",
+        handler: ShortcutHandler::Canvas(|mut application| {
+            let mut application = application.write();
+            let mut graph = application.graph;
+            graph.clear_dragging();
+            application.palette.close();
+            application.shortcut_help_open = false;
+            application.canvas_search = Default::default();
+        }),
+    },
+    Shortcut {
+        keys: Keys::arrows(),
+        description: "Move the focused node, like dragging it",
+        handler: ShortcutHandler::Node(|_, mut node, evt| {
+            if let Some(offset) = nudge(&evt.key()) {
+                node.write().position += offset.to_vector();
+            }
+        }),
+    },
+    Shortcut {
+        keys: Keys::enter(),
+        description: "Show the details of the focused node",
+        handler: ShortcutHandler::Node(|mut application, node, _| {
+            let id = node.read().id;
+            application.write().focus_node(id);
+        }),
+    },
+    Shortcut {
+        keys: Keys::enter().with_shift(),
+        description: "Add the focused node to the selection or remove it, like shift clicking it",
+        handler: ShortcutHandler::Node(|application, node, _| {
+            let id = node.read().id;
+            let mut inner = application.read().graph.inner;
+            inner.write().toggle_selected(id);
+        }),
+    },
+    Shortcut {
+        keys: Keys::enter(),
+        description: "Pick up a connection from the focused socket, or drop the connection on it",
+        handler: ShortcutHandler::Socket(|application, node, index| {
+            let mut graph = application.read().graph;
+            graph.toggle_keyboard_connection(node, index);
+        }),
+    },
+];
+
+pub struct Shortcut {
+    pub keys: Keys,
+    pub description: &'static str,
+    pub handler: ShortcutHandler,
+}
+
+/// What a key event happened on
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShortcutTarget {
+    Canvas,
+    /// A node that has keyboard focus
+    Node(Signal<Node>),
+    /// A socket of a node that has keyboard focus
+    Socket(Signal<Node>, DraggingIndex),
+}
+
+/// The code a shortcut runs. Shortcuts only run for key events on the kind of target their
+/// handler accepts
+#[derive(Clone, Copy)]
+pub enum ShortcutHandler {
+    Canvas(fn(Signal<ApplicationState>)),
+    Node(fn(Signal<ApplicationState>, Signal<Node>, &KeyboardData)),
+    Socket(fn(Signal<ApplicationState>, Signal<Node>, DraggingIndex)),
+}
+
+impl ShortcutHandler {
+    /// Run the handler if it accepts the target. Returns true if it was run
+    fn run(
+        self,
+        application: Signal<ApplicationState>,
+        target: ShortcutTarget,
+        evt: &KeyboardData,
+    ) -> bool {
+        match (self, target) {
+            (ShortcutHandler::Canvas(handler), ShortcutTarget::Canvas) => handler(application),
+            (ShortcutHandler::Node(handler), ShortcutTarget::Node(node)) => {
+                handler(application, node, evt)
+            }
+            (ShortcutHandler::Socket(handler), ShortcutTarget::Socket(node, index)) => {
+                handler(application, node, index)
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Keys {
+    /// If the platform leader key (Ctrl or Cmd) must be held
+    pub leader: bool,
+    /// If shift must be held. Shift is part of typing some characters, so it is ignored for
+    /// character keys
+    pub shift: bool,
+    pub key: ShortcutKey,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShortcutKey {
+    Character(&'static str),
+    Escape,
+    Enter,
+    /// Any of the four arrow keys
+    Arrows,
+}
+
+impl Keys {
+    const fn new(key: ShortcutKey) -> Self {
+        Self {
+            leader: false,
+            shift: false,
+            key,
+        }
+    }
+
+    pub const fn character(character: &'static str) -> Self {
+        Self::new(ShortcutKey::Character(character))
+    }
+
+    pub const fn leader(character: &'static str) -> Self {
+        Self {
+            leader: true,
+            ..Self::character(character)
+        }
+    }
+
+    pub const fn escape() -> Self {
+        Self::new(ShortcutKey::Escape)
+    }
+
+    pub const fn enter() -> Self {
+        Self::new(ShortcutKey::Enter)
+    }
+
+    pub const fn arrows() -> Self {
+        Self::new(ShortcutKey::Arrows)
+    }
+
+    pub const fn with_shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+
+    pub fn matches(&self, evt: &KeyboardData) -> bool {
+        let modifiers = evt.modifiers();
+        let leader_held =
+            modifiers.contains(Modifiers::CONTROL) || modifiers.contains(Modifiers::META);
+        if leader_held != self.leader {
+            return false;
+        }
+        let shift_held = modifiers.contains(Modifiers::SHIFT);
+        if !matches!(self.key, ShortcutKey::Character(_)) && shift_held != self.shift {
+            return false;
+        }
+        match (self.key, evt.key()) {
+            (ShortcutKey::Character(expected), Key::Character(pressed)) => {
+                pressed.eq_ignore_ascii_case(expected)
+            }
+            (ShortcutKey::Escape, Key::Escape) => true,
+            (ShortcutKey::Enter, Key::Enter) => true,
+            (ShortcutKey::Arrows, key) => nudge(&key).is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let mut key = match self.key {
+            ShortcutKey::Character(character) => character.to_uppercase(),
+            ShortcutKey::Escape => "Esc".to_string(),
+            ShortcutKey::Enter => "Enter".to_string(),
+            ShortcutKey::Arrows => "Arrow keys".to_string(),
+        };
+        if self.shift {
+            key = format!("Shift+{key}");
+        }
+        if self.leader {
+            let leader = if cfg!(target_os = "macos") {
+                "Cmd"
+            } else {
+                "Ctrl"
+            };
+            format!("{leader}+{key}")
+        } else {
+            key
+        }
+    }
+}
+
+/// Run the first shortcut for the target that matches the key event. Returns true if a shortcut
+/// was run
+pub fn handle_shortcut(
+    application: Signal<ApplicationState>,
+    target: ShortcutTarget,
+    evt: &KeyboardData,
+) -> bool {
+    SHORTCUTS
+        .iter()
+        .filter(|shortcut| shortcut.keys.matches(evt))
+        .any(|shortcut| shortcut.handler.run(application, target, evt))
+}
+
+/// Key handler for text boxes on top of the canvas. Typing in them shouldn't trigger canvas
+/// shortcuts, but escape still goes through so it can close them
+pub fn keep_typing_out_of_shortcuts(evt: &KeyboardEvent) {
+    if evt.key() != Key::Escape {
+        evt.stop_propagation();
+    }
+}
+
+pub fn ShortcutHelp() -> Element {
+    let mut application = use_application_state();
+    if !application.read().shortcut_help_open {
+        return None;
+    }
+
+    rsx! {
+        div {
+            class: "fixed inset-0 z-30 flex items-center justify-center bg-black/25",
+            onmousedown: move |evt| evt.stop_propagation(),
+            onclick: move |_| application.write().shortcut_help_open = false,
+            div {
+                class: "bg-white border rounded-md shadow-lg p-4 w-96",
+                onclick: move |evt| evt.stop_propagation(),
+                h2 { class: "text-xl font-bold mb-2", "Keyboard shortcuts" }
+                ul { role: "list", class: "divide-y",
+                    for shortcut in SHORTCUTS {
+                        li { class: "flex flex-row justify-between py-2 text-sm",
+                            span { "{shortcut.description}" }
+                            kbd { class: "border rounded-md px-1 font-mono", "{shortcut.keys.label()}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn shortcuts_for_the_same_target_use_different_keys() {
+    use std::mem::discriminant;
+
+    for (index, first) in SHORTCUTS.iter().enumerate() {
+        for second in &SHORTCUTS[index + 1..] {
+            let same_target = discriminant(&first.handler) == discriminant(&second.handler);
+            assert!(
+                !same_target || first.keys != second.keys,
+                "{:?} is used by both {:?} and {:?}",
+                first.keys.label(),
+                first.description,
+                second.description
+            );
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::plugin_search::PluginSearch;
 // use crate::share::SaveMenu;
+use crate::validate::ProblemsPanel;
 use crate::CurrentNodeInfo;
 use dioxus::prelude::*;
 
@@ -11,6 +12,8 @@ enum SidebarRoute {
         PluginSearch {},
         #[route("/node")]
         CurrentNodeInfo {},
+        #[route("/problems")]
+        ProblemsPanel {},
         // #[route("/save")]
         // SaveMenu {}
 }
@@ -59,6 +62,11 @@ document.addEventListener("mouseup", function(){
                     to: SidebarRoute::CurrentNodeInfo {},
                     "Current Node"
                 }
+                Link {
+                    class: "px-3 py-2 text-sm font-medium w-full",
+                    to: SidebarRoute::ProblemsPanel {},
+                    "Problems"
+                }
             }
             Outlet::<SidebarRoute> {}
         }
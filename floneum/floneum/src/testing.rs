@@ -0,0 +1,93 @@
+//! Fixtures shared by the tests of the graph editor
+
+use std::future::Future;
+use std::path::Path;
+
+use dioxus::prelude::*;
+use floneum_plugin::plugins::main::types::{IoDefinition, PrimitiveValueType, ValueType};
+use floneum_plugin::{load_plugin, Plugin};
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+
+use crate::edge::{Connection, ConnectionType};
+use crate::shape::{GraphShape, InputShape, NodeShape};
+use crate::{Edge, Point};
+
+pub fn text() -> ValueType {
+    ValueType::Single(PrimitiveValueType::Text)
+}
+
+pub fn number() -> ValueType {
+    ValueType::Single(PrimitiveValueType::Number)
+}
+
+/// A connection to a single value input
+pub fn input(index: usize) -> Connection {
+    Connection {
+        index,
+        ty: ConnectionType::Single,
+    }
+}
+
+impl NodeShape {
+    /// Create a node at the origin with the given input and output types. Every input has a value.
+    pub fn test(inputs: &[ValueType], outputs: &[ValueType]) -> Self {
+        Self {
+            name: "test".to_string(),
+            position: Point::new(0., 0.),
+            rendered_size: None,
+            inputs: inputs
+                .iter()
+                .enumerate()
+                .map(|(i, &ty)| InputShape {
+                    definition: IoDefinition {
+                        name: format!("input {i}"),
+                        ty,
+                    },
+                    has_value: true,
+                })
+                .collect(),
+            outputs: outputs
+                .iter()
+                .enumerate()
+                .map(|(i, &ty)| IoDefinition {
+                    name: format!("output {i}"),
+                    ty,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Two connected text nodes and a number node that isn't connected to anything
+pub fn test_graph() -> (GraphShape, [NodeIndex<DefaultIx>; 3]) {
+    let mut graph = GraphShape::default();
+    let first = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let second = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let numbers = graph.add_node(NodeShape::test(&[number()], &[number()]));
+    graph.add_edge(first, second, Edge::new(0, input(0)));
+    (graph, [first, second, numbers])
+}
+
+/// Run a test in the root scope of an empty virtual dom so it can create signals and spawn tasks
+/// the same way the app does
+pub fn run_in_dioxus<F: Future>(test: impl FnOnce() -> F) -> F::Output {
+    let dom = VirtualDom::new(|| None);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    dom.in_runtime(|| ScopeId::ROOT.in_runtime(|| runtime.block_on(test())))
+        .expect("the root scope is created with the virtual dom")
+}
+
+/// Load one of the plugins that are built into the dist folder at the root of the repository
+pub fn test_plugin(name: &str) -> Plugin {
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let crate_dir: &Path = root.as_ref();
+    let path = crate_dir
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("dist")
+        .join(name)
+        .join("package.wasm");
+    load_plugin(&path, Default::default())
+}
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+use petgraph::{graph::NodeIndex, stable_graph::DefaultIx};
+
+use crate::ApplicationState;
+
+/// How many steps the undo history remembers
+const UNDO_LIMIT: usize = 50;
+
+/// A change to the graph that can be taken back
+#[derive(Clone, PartialEq, Debug)]
+pub enum UndoStep {
+    /// Nodes that were added together, like the copies made by duplicating the selection. Undoing
+    /// removes them with their connections
+    AddNodes(Vec<NodeIndex<DefaultIx>>),
+}
+
+/// The changes that can be undone, oldest first
+#[derive(Default)]
+pub struct UndoHistory {
+    steps: VecDeque<UndoStep>,
+}
+
+impl UndoHistory {
+    pub fn record(&mut self, step: UndoStep) {
+        self.steps.push_back(step);
+        if self.steps.len() > UNDO_LIMIT {
+            self.steps.pop_front();
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<UndoStep> {
+        self.steps.pop_back()
+    }
+
+    /// Forget a node that was removed from the graph. The graph reuses the ids of removed nodes,
+    /// so undoing a step must never remove a node that was added later with the same id
+    pub fn forget(&mut self, id: NodeIndex<DefaultIx>) {
+        for step in &mut self.steps {
+            match step {
+                UndoStep::AddNodes(nodes) => nodes.retain(|&node| node != id),
+            }
+        }
+        self.steps
+            .retain(|step| !matches!(step, UndoStep::AddNodes(nodes) if nodes.is_empty()));
+    }
+}
+
+impl ApplicationState {
+    /// Take back the last recorded change. Returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(step) = self.undo_history.pop() else {
+            return false;
+        };
+        match step {
+            UndoStep::AddNodes(nodes) => {
+                for id in nodes {
+                    self.remove(id);
+                }
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn removed_nodes_are_forgotten() {
+    let mut history = UndoHistory::default();
+    let (first, second) = (NodeIndex::new(0), NodeIndex::new(1));
+    history.record(UndoStep::AddNodes(vec![first]));
+    history.record(UndoStep::AddNodes(vec![first, second]));
+
+    history.forget(first);
+    assert_eq!(history.pop(), Some(UndoStep::AddNodes(vec![second])));
+    // The step that only added the removed node is gone
+    assert_eq!(history.pop(), None);
+}
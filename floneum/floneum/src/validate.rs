@@ -0,0 +1,177 @@
+use dioxus::prelude::*;
+use petgraph::{
+    algo::tarjan_scc,
+    graph::NodeIndex,
+    stable_graph::DefaultIx,
+    visit::{EdgeRef, IntoEdgeReferences},
+};
+
+use crate::edge::ConnectionType;
+use crate::shape::GraphShape;
+use crate::{use_application_state, VisualGraph};
+
+/// An issue with the graph that should be fixed before it is run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub node: NodeIndex<DefaultIx>,
+    pub message: String,
+}
+
+impl VisualGraph {
+    /// Check the graph for cycles, missing inputs, incompatible connections and orphaned nodes
+    pub fn validate(&self) -> Vec<Problem> {
+        find_problems(&self.inner.read().shape())
+    }
+}
+
+pub fn find_problems(graph: &GraphShape) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    // Cycles
+    for component in tarjan_scc(graph) {
+        let is_cycle = component.len() > 1
+            || component
+                .first()
+                .is_some_and(|&id| graph.contains_edge(id, id));
+        if is_cycle {
+            let node = *component.iter().min().unwrap();
+            problems.push(Problem {
+                node,
+                message: format!(
+                    "{} is part of a cycle through {} node(s)",
+                    graph[node].name,
+                    component.len()
+                ),
+            });
+        }
+    }
+
+    for id in graph.node_indices() {
+        let node = &graph[id];
+
+        // Inputs that have no value and nothing connected to them
+        for (index, input) in node.inputs.iter().enumerate() {
+            let connected = graph
+                .edges_directed(id, petgraph::Direction::Incoming)
+                .any(|edge| edge.weight().end.index == index);
+            if !connected && !input.has_value {
+                problems.push(Problem {
+                    node: id,
+                    message: format!(
+                        "{} is missing a value for the input {}",
+                        node.name, input.definition.name
+                    ),
+                });
+            }
+        }
+
+        // Nodes that are not connected to anything else in the graph
+        if graph.node_count() > 1 && graph.neighbors_undirected(id).next().is_none() {
+            problems.push(Problem {
+                node: id,
+                message: format!("{} is not connected to any other node", node.name),
+            });
+        }
+    }
+
+    // Connections whose types no longer line up (for example after a plugin changed)
+    for edge in graph.edge_references() {
+        let weight = edge.weight();
+        let source = &graph[edge.source()];
+        let target = &graph[edge.target()];
+        let output_ty = source.output_type(weight.start);
+        let input_ty = target.input_type(weight.end);
+        let compatible = match (output_ty, input_ty) {
+            (Some(output_ty), Some(input_ty)) => output_ty.compatible(&input_ty),
+            _ => false,
+        };
+        if !compatible {
+            let input_name = target
+                .inputs
+                .get(weight.end.index)
+                .map(|input| input.definition.name.as_str())
+                .unwrap_or("unknown");
+            let input_name = match weight.end.ty {
+                ConnectionType::Single => input_name.to_string(),
+                ConnectionType::Element(index) => format!("{input_name}[{index}]"),
+            };
+            let output_name = source
+                .outputs
+                .get(weight.start)
+                .map(|output| output.name.as_str())
+                .unwrap_or("unknown");
+            problems.push(Problem {
+                node: edge.target(),
+                message: format!(
+                    "The input {input_name} of {} is connected to the incompatible output {output_name} of {}",
+                    target.name, source.name
+                ),
+            });
+        }
+    }
+
+    problems
+}
+
+pub fn ProblemsPanel() -> Element {
+    let mut application = use_application_state();
+    let mut problems = use_signal(|| Option::<Vec<Problem>>::None);
+
+    rsx! {
+        div { class: "flex flex-col p-2",
+            button {
+                class: "border rounded-md p-2 m-2",
+                onclick: move |_| {
+                    let graph = application.read().graph;
+                    problems.set(Some(graph.validate()));
+                },
+                "Validate"
+            }
+            match &*problems.read() {
+                Some(problems) if problems.is_empty() => rsx! {
+                    p { "No problems found" }
+                },
+                Some(problems) => rsx! {
+                    ul { role: "list", class: "divide-y divide-black text-left",
+                        for problem in problems.iter().cloned() {
+                            li { class: "px-3 py-2",
+                                button {
+                                    class: "w-full text-left text-sm",
+                                    onclick: move |_| application.write().focus_node(problem.node),
+                                    "{problem.message}"
+                                }
+                            }
+                        }
+                    }
+                },
+                None => rsx! {
+                    p { class: "text-sm opacity-50", "Check the workflow for problems before running it" }
+                },
+            }
+        }
+    }
+}
+
+#[test]
+fn cycle_and_missing_input() {
+    use crate::shape::NodeShape;
+    use crate::testing::{input, text};
+    use crate::Edge;
+
+    let mut graph = GraphShape::default();
+    let first = graph.add_node(NodeShape::test(&[text()], &[text()]));
+    let mut second = NodeShape::test(&[text(), text()], &[text()]);
+    second.inputs[1].has_value = false;
+    let second = graph.add_node(second);
+    graph.add_edge(first, second, Edge::new(0, input(0)));
+    graph.add_edge(second, first, Edge::new(0, input(0)));
+
+    let problems = find_problems(&graph);
+    assert_eq!(problems.len(), 2);
+    assert!(problems
+        .iter()
+        .any(|problem| problem.node == first && problem.message.contains("cycle")));
+    assert!(problems
+        .iter()
+        .any(|problem| problem.node == second && problem.message.contains("missing")));
+}
@@ -83,6 +83,44 @@ pub(crate) fn make_config() -> anyhow::Result<dioxus::desktop::Config> {
         overscroll-behavior-x: none;
         overscroll-behavior-y: none;
     }
+    @keyframes node-enter {
+        from {
+            opacity: 0;
+            transform: scale(0.9);
+        }
+        to {
+            opacity: 1;
+            transform: scale(1);
+        }
+    }
+    .node-enter {
+        animation: node-enter 150ms ease-out;
+    }
+    .node-exit {
+        opacity: 0;
+        transform: scale(0.9);
+        transition: opacity 150ms ease-in, transform 150ms ease-in;
+        pointer-events: none;
+    }
+    @keyframes connection-stale {
+        to {
+            stroke-dashoffset: -20;
+        }
+    }
+    .connection-stale {
+        animation: connection-stale 1s linear infinite;
+    }
+    @media (prefers-reduced-motion: reduce) {
+        .node-enter {
+            animation: none;
+        }
+        .node-exit {
+            transition: none;
+        }
+        .connection-stale {
+            animation: none;
+        }
+    }
 </style>
 <style type="text/css">
 "#
@@ -61,6 +61,7 @@ impl PluginMetadata {
     }
 }
 
+#[derive(Clone)]
 pub struct Plugin {
     shared: SharedPluginState,
     source: PackageIndexEntry,
@@ -120,7 +121,7 @@ impl Plugin {
         Ok(self.component.get().unwrap())
     }
 
-    async fn definition(&self) -> anyhow::Result<&Definition> {
+    pub async fn definition(&self) -> anyhow::Result<&Definition> {
         if let Some(metadata) = self.definition.get() {
             return Ok(metadata);
         }
@@ -133,6 +134,11 @@ impl Plugin {
         Ok(self.definition.get().unwrap())
     }
 
+    /// The definition of the plugin if it has already been loaded
+    pub fn loaded_definition(&self) -> Option<&Definition> {
+        self.definition.get()
+    }
+
     pub async fn metadata(&self) -> anyhow::Result<&PluginMetadata> {
         if let Some(metadata) = self.metadata.get() {
             return Ok(metadata);